@@ -0,0 +1,74 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Run with `cargo bench --features bench`. Larger corpora can be timed
+//! with `sudoku batch`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use sudoku::batch;
+use sudoku::sudoku_alg;
+
+const CORPUS: &str = include_str!("corpus.txt");
+
+fn puzzles() -> Vec<Vec<usize>> {
+    CORPUS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| sudoku::text::parse(line).unwrap().cells().to_vec())
+        .collect()
+}
+
+fn dancing_links(c: &mut Criterion) {
+    c.bench_function("dancing_links/new", |b| {
+        b.iter(|| sudoku_alg::matrix().unwrap())
+    });
+
+    // every column in turn, so the numbers don't depend on one column's size
+    c.bench_function("dancing_links/cover_uncover", |b| {
+        let mut dlx = sudoku_alg::matrix().unwrap();
+        b.iter(|| {
+            for column in 0..dlx.width() {
+                dlx.cover_column(black_box(column)).unwrap();
+                dlx.uncover_column(column).unwrap();
+            }
+        })
+    });
+}
+
+fn solve(c: &mut Criterion) {
+    let puzzles = puzzles();
+
+    c.bench_function("solve/hardest", |b| {
+        b.iter_batched(
+            || puzzles[0].clone(),
+            |puzzle| sudoku_alg::solve(puzzle).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("solve/corpus", |b| {
+        b.iter(|| {
+            for puzzle in &puzzles {
+                sudoku_alg::solve(puzzle.clone()).unwrap();
+            }
+        })
+    });
+
+    c.bench_function("solve/batch", |b| {
+        b.iter(|| batch::solve_corpus(black_box(CORPUS.as_bytes())).unwrap())
+    });
+}
+
+criterion_group!(benches, dancing_links, solve);
+criterion_main!(benches);
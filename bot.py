@@ -78,7 +78,7 @@ print(f'Time to process image: {time.time() - start_time}')
 start_time = time.time()
 
 # use OCR to fill in puzzle
-unsolved_puzzle = np.zeros(81).astype(int)
+unsolved_puzzle = sudoku.Sudoku([0] * 81)
 for i in range(0, 9):
     for j in range(0, 9):
         # calculate cell bounds (3px border trimmed)
@@ -96,13 +96,13 @@ for i in range(0, 9):
                 cell, config='--psm 10 -c tessedit_char_whitelist=123456789').strip()
 
         # save cell to puzzle
-        unsolved_puzzle[i * 9 + j] = int(text)
+        unsolved_puzzle = unsolved_puzzle.with_cell(i, j, int(text))
 
 print(f'Time to extract OCR: {time.time() - start_time}')
 start_time = time.time()
 
 # solve sudoku puzzle
-solved_puzzle = sudoku.solve(unsolved_puzzle)
+solved_puzzle = unsolved_puzzle.solve()
 
 print(f'Time to solve puzzle: {time.time() - start_time}')
 start_time = time.time()
@@ -114,11 +114,11 @@ for i in range(0, 9):
         x1 = x + dW * (j + 0.5) + offset_x
         y1 = y + dH * (i + 0.5) + offset_y
 
-        if unsolved_puzzle[i * 9 + j] == 0:
+        if unsolved_puzzle[i, j] == 0:
             # click on cell
             gui.click(x1, y1)
             # enter key
-            gui.press(str(solved_puzzle[i * 9 + j]))
+            gui.press(str(solved_puzzle[i, j]))
 
 print(f'Time to input answers: {time.time() - start_time}')
 
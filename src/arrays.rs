@@ -0,0 +1,181 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use crate::puzzle::Sudoku;
+use crate::sudoku_alg::Error;
+use numpy::{npyffi, Element, PyArray, PyArrayDyn};
+use pyo3::{
+    create_exception,
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    AsPyPointer,
+};
+
+create_exception!(
+    sudoku,
+    ShapeError,
+    PyValueError,
+    "Raised when a NumPy puzzle isn't a length 81 or 9x9 array."
+);
+create_exception!(
+    sudoku,
+    DtypeError,
+    PyTypeError,
+    "Raised when a NumPy puzzle doesn't have an integer dtype."
+);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Dtype {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+/// Cells read from a NumPy array, along with the array's shape and dtype so
+/// results can be returned in the same form.
+#[derive(Debug, Clone)]
+pub struct PuzzleArray {
+    pub cells: Vec<usize>,
+    shape: Vec<usize>,
+    dtype: Dtype,
+}
+
+pub fn is_array(obj: &PyAny) -> bool {
+    // only ask NumPy if it's already loaded, so plain lists work without it
+    let loaded = obj
+        .py()
+        .import("sys")
+        .and_then(|sys| sys.getattr("modules"))
+        .and_then(|modules| modules.contains("numpy"))
+        .unwrap_or(false);
+
+    loaded && unsafe { npyffi::PyArray_Check(obj.py(), obj.as_ptr()) != 0 }
+}
+
+// The buffer is read in place rather than through Python objects, but the
+// cells are still copied, since the solver works on `usize` cells whatever
+// the dtype. That's 81 conversions, next to a search of thousands of nodes.
+fn read<T>(array: &PyArrayDyn<T>) -> PyResult<Vec<usize>>
+where
+    T: Element + Copy + std::fmt::Display + TryInto<usize>,
+{
+    let convert = |n: &T| {
+        (*n).try_into()
+            .map_err(|_| PyValueError::new_err(format!("invalid cell: got {n}!")))
+    };
+    let view = array.readonly();
+
+    // contiguous arrays, the usual case, are a plain slice of the buffer
+    match view.as_slice() {
+        Ok(cells) => cells.iter().map(convert).collect(),
+        Err(_) => view.as_array().iter().map(convert).collect(),
+    }
+}
+
+fn check_shape(shape: &[usize]) -> PyResult<()> {
+    if shape != [81] && shape != [9, 9] {
+        return Err(ShapeError::new_err(format!(
+            "puzzle arrays must have shape (81,) or (9, 9), got {shape:?}"
+        )));
+    }
+
+    Ok(())
+}
+
+fn write<T>(py: Python<'_>, cells: &[usize], shape: &[usize]) -> PyResult<PyObject>
+where
+    T: Element + TryFrom<usize>,
+{
+    let data = cells
+        .iter()
+        .map(|n| T::try_from(*n).map_err(|_| Error::InvalidCell { got: *n }))
+        .collect::<Result<Vec<T>, Error>>()?;
+
+    Ok(PyArray::from_vec(py, data).reshape(shape)?.into_py(py))
+}
+
+pub fn read_puzzle(obj: &PyAny) -> PyResult<PuzzleArray> {
+    macro_rules! try_dtypes {
+        ($($ty:ty => $dtype:ident),*) => {
+            $(
+                if let Ok(array) = obj.downcast::<PyArrayDyn<$ty>>() {
+                    // check the shape before reading any cells
+                    check_shape(array.shape())?;
+                    (read(array)?, array.shape().to_vec(), Dtype::$dtype)
+                } else
+            )*
+            {
+                return Err(DtypeError::new_err(
+                    "puzzle arrays must have an integer dtype".to_owned(),
+                ));
+            }
+        };
+    }
+
+    let (cells, shape, dtype) = try_dtypes!(
+        i8 => I8, i16 => I16, i32 => I32, i64 => I64,
+        u8 => U8, u16 => U16, u32 => U32, u64 => U64
+    );
+
+    Ok(PuzzleArray {
+        cells,
+        shape,
+        dtype,
+    })
+}
+
+/// Returns `cells` as a NumPy array with the same shape and dtype as `like`.
+pub fn write_puzzle(py: Python<'_>, cells: &[usize], like: &PuzzleArray) -> PyResult<PyObject> {
+    let shape = &like.shape[..];
+
+    match like.dtype {
+        Dtype::I8 => write::<i8>(py, cells, shape),
+        Dtype::I16 => write::<i16>(py, cells, shape),
+        Dtype::I32 => write::<i32>(py, cells, shape),
+        Dtype::I64 => write::<i64>(py, cells, shape),
+        Dtype::U8 => write::<u8>(py, cells, shape),
+        Dtype::U16 => write::<u16>(py, cells, shape),
+        Dtype::U32 => write::<u32>(py, cells, shape),
+        Dtype::U64 => write::<u64>(py, cells, shape),
+    }
+}
+
+/// Runs `f` on a puzzle given as a list, a NumPy array or a `Sudoku` with
+/// the GIL released, returning the result in the same form.
+pub fn map_puzzle<F>(py: Python<'_>, puzzle: &PyAny, f: F) -> PyResult<PyObject>
+where
+    F: FnOnce(Vec<usize>) -> Result<[usize; 81], Error> + Send,
+{
+    if is_array(puzzle) {
+        let array = read_puzzle(puzzle)?;
+        let cells = array.cells.clone();
+        let solution = py.allow_threads(|| f(cells))?;
+
+        write_puzzle(py, &solution, &array)
+    } else if let Ok(puzzle) = puzzle.extract::<Sudoku>() {
+        let solution = py.allow_threads(|| f(puzzle.cells().to_vec()))?;
+
+        Ok(Sudoku::new(solution)?.into_py(py))
+    } else {
+        let cells: Vec<usize> = puzzle.extract()?;
+        let solution = py.allow_threads(|| f(cells))?;
+
+        Ok(solution.into_py(py))
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Solves a corpus of puzzles, such as top95 or the 17-clue collection, and
+//! reports how fast and how hard the search was.
+//!
+//! Corpora have one puzzle per line, as 81 characters with `0` or `.` for
+//! empty cells. Anything after the first whitespace on a line is ignored, as
+//! are blank lines and lines starting with `#`.
+
+use crate::puzzle::Sudoku;
+use crate::sudoku_alg;
+use crate::text;
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    Io { msg: String },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { msg } => write!(f, "can't read corpus: {msg}!"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A puzzle that couldn't be parsed or solved, or whose solution was wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// line of the corpus, counting from 1
+    pub line: usize,
+    pub msg: String,
+}
+
+/// What happened while solving a corpus. Times only cover solving, not
+/// reading or checking.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// puzzles read, including those that failed
+    pub puzzles: usize,
+    pub failures: Vec<Failure>,
+    /// time to solve each puzzle that was solved, in corpus order
+    pub times: Vec<Duration>,
+    /// search tree nodes visited, over all puzzles
+    pub nodes: usize,
+    /// link updates performed, over all puzzles
+    pub updates: usize,
+    /// deepest search of any puzzle
+    pub max_depth: usize,
+}
+
+impl Report {
+    pub fn total(&self) -> Duration {
+        self.times.iter().sum()
+    }
+
+    /// Puzzles solved per second.
+    pub fn throughput(&self) -> f64 {
+        match self.total().as_secs_f64() {
+            secs if secs > 0.0 => self.times.len() as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// The time within which `percent` of puzzles were solved, using the
+    /// nearest rank. Returns zero if nothing was solved.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        let mut times = self.times.clone();
+        times.sort_unstable();
+
+        let rank = (percent / 100.0 * times.len() as f64).ceil() as usize;
+        match times.len() {
+            0 => Duration::ZERO,
+            len => times[rank.clamp(1, len) - 1],
+        }
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let solved = self.puzzles - self.failures.len();
+        let per_puzzle = |total: usize| total as f64 / self.times.len().max(1) as f64;
+
+        writeln!(f, "puzzles:     {} ({solved} solved)", self.puzzles)?;
+        writeln!(f, "total time:  {:.3?}", self.total())?;
+        writeln!(f, "throughput:  {:.1} puzzles/s", self.throughput())?;
+        writeln!(
+            f,
+            "time p50:    {:.3?}, p90: {:.3?}, p99: {:.3?}, max: {:.3?}",
+            self.percentile(50.0),
+            self.percentile(90.0),
+            self.percentile(99.0),
+            self.percentile(100.0)
+        )?;
+        writeln!(
+            f,
+            "search:      {:.1} nodes and {:.1} updates per puzzle, depth up to {}",
+            per_puzzle(self.nodes),
+            per_puzzle(self.updates),
+            self.max_depth
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "line {}: {}", failure.line, failure.msg)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Solves each puzzle of a corpus as it's read, checking that the solution
+/// fills the grid without conflicts and keeps the givens.
+pub fn solve_corpus<R: BufRead>(corpus: R) -> Result<Report, Error> {
+    let mut report = Report::default();
+
+    for (i, line) in corpus.lines().enumerate() {
+        let line = line.map_err(|err| Error::Io {
+            msg: err.to_string(),
+        })?;
+        let puzzle = match line.split_whitespace().next() {
+            Some(puzzle) if !puzzle.starts_with('#') => puzzle,
+            _ => continue,
+        };
+        report.puzzles += 1;
+
+        let puzzle = match text::parse(puzzle) {
+            Ok(puzzle) => puzzle,
+            Err(err) => {
+                report.failures.push(Failure {
+                    line: i + 1,
+                    msg: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let result = sudoku_alg::solve_with_stats(puzzle.cells().to_vec());
+        let elapsed = start.elapsed();
+
+        match result {
+            Ok((solution, stats)) => {
+                // only solved puzzles count towards the timings
+                report.times.push(elapsed);
+                if let Err(msg) = check(&puzzle, &solution) {
+                    report.failures.push(Failure { line: i + 1, msg });
+                }
+                report.nodes += stats.nodes;
+                report.updates += stats.updates;
+                report.max_depth = report.max_depth.max(stats.max_depth);
+            }
+            Err(err) => report.failures.push(Failure {
+                line: i + 1,
+                msg: err.to_string(),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+fn check(puzzle: &Sudoku, solution: &[usize; 81]) -> Result<(), String> {
+    let solution = Sudoku::new(*solution).map_err(|err| err.to_string())?;
+
+    if solution.cells().contains(&0) || !solution.is_valid() {
+        return Err("solution isn't a complete grid!".to_owned());
+    }
+    if let Some(i) =
+        (0..81).find(|i| puzzle.cells()[*i] != 0 && puzzle.cells()[*i] != solution.cells()[*i])
+    {
+        return Err(format!("solution changes the given in cell {i}!"));
+    }
+
+    Ok(())
+}
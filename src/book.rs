@@ -0,0 +1,370 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Lays out puzzles as a printable PDF book, with the solutions at the back.
+//!
+//! Each page is drawn as SVG by [`render`] and converted with
+//! svg2pdf, then the pages are put together with pdf-writer.
+
+use crate::puzzle::Sudoku;
+use crate::render::{self, escape, Options};
+use crate::sudoku_alg::Error as SudokuError;
+use crate::variant::Variant;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A4 in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 40.0;
+/// room for the book title above the puzzles
+const HEADER: f64 = 40.0;
+/// room for the page number below the puzzles
+const FOOTER: f64 = 24.0;
+/// room for the label above each puzzle
+const LABEL: f64 = 22.0;
+/// space between puzzles
+const GAP: f64 = 16.0;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    InvalidLayout { per_page: usize },
+    Unsolvable { title: String, inner: SudokuError },
+    Render { inner: render::Error },
+    Pdf { msg: String },
+    Io { path: String, msg: String },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidLayout { per_page } => {
+                write!(f, "invalid layout: got {per_page} puzzles per page!")
+            }
+            Error::Unsolvable { title, inner } => {
+                write!(f, "can't solve {title:?} for the solution pages: {inner}")
+            }
+            Error::Render { inner } => write!(f, "render error: {inner}"),
+            Error::Pdf { msg } => write!(f, "can't convert page to PDF: {msg}!"),
+            Error::Io { path, msg } => write!(f, "can't access {path:?}: {msg}!"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Unsolvable { inner, .. } => Some(inner),
+            Error::Render { inner } => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl From<render::Error> for Error {
+    fn from(error: render::Error) -> Self {
+        Error::Render { inner: error }
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io { .. } => PyErr::new::<PyOSError, _>(err.to_string()),
+            _ => PyErr::new::<PyValueError, _>(err.to_string()),
+        }
+    }
+}
+
+// the number of columns that lets puzzles be drawn largest, and their size
+fn layout(per_page: usize) -> (usize, f64) {
+    (1..=per_page)
+        .map(|cols| {
+            let rows = per_page.div_ceil(cols);
+            let slot_width = (PAGE_WIDTH - 2.0 * MARGIN) / cols as f64;
+            let slot_height = (PAGE_HEIGHT - 2.0 * MARGIN - HEADER - FOOTER) / rows as f64;
+            (cols, (slot_width - GAP).min(slot_height - LABEL - GAP))
+        })
+        .fold(
+            (1, f64::MIN),
+            |best, next| if next.1 > best.1 { next } else { best },
+        )
+}
+
+/// A puzzle in a book, with the label printed above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub puzzle: Variant,
+    pub title: String,
+    /// printed after the title, such as "Easy" or "Diabolical"
+    pub difficulty: Option<String>,
+}
+
+impl Entry {
+    fn label(&self) -> String {
+        match &self.difficulty {
+            Some(difficulty) => format!("{} · {difficulty}", self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// A puzzle book, printed on A4 pages.
+#[pyclass(module = "sudoku")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Book {
+    /// printed at the top of every page
+    #[pyo3(get, set)]
+    pub title: String,
+    #[pyo3(get, set)]
+    pub per_page: usize,
+    #[pyo3(get, set)]
+    pub solutions_per_page: usize,
+    pub entries: Vec<Entry>,
+    /// how puzzles are drawn; `cell_size` doesn't matter since puzzles are
+    /// scaled to fit the page
+    pub options: Options,
+}
+
+impl Book {
+    /// An empty book with four puzzles and six solutions per page.
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            per_page: 4,
+            solutions_per_page: 6,
+            entries: Vec::new(),
+            options: Options::default(),
+        }
+    }
+
+    /// Adds a puzzle, titled "Puzzle N" unless `title` is given.
+    pub fn add(&mut self, puzzle: Variant, title: Option<&str>, difficulty: Option<&str>) {
+        let title = match title {
+            Some(title) => title.to_owned(),
+            None => format!("Puzzle {}", self.entries.len() + 1),
+        };
+
+        self.entries.push(Entry {
+            puzzle,
+            title,
+            difficulty: difficulty.map(str::to_owned),
+        });
+    }
+
+    /// The SVG of every page: the puzzles, then their solutions.
+    pub fn pages(&self) -> Result<Vec<String>, Error> {
+        for per_page in [self.per_page, self.solutions_per_page] {
+            if per_page == 0 {
+                return Err(Error::InvalidLayout { per_page });
+            }
+        }
+
+        let mut solutions = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let solution = entry.puzzle.solve().map_err(|inner| Error::Unsolvable {
+                title: entry.title.clone(),
+                inner,
+            })?;
+            solutions.push(solution);
+        }
+
+        let puzzles: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| (entry, Default::default()))
+            .collect();
+        let solutions: Vec<_> = self.entries.iter().zip(solutions).collect();
+
+        let mut pages = Vec::new();
+        for chunk in puzzles.chunks(self.per_page) {
+            pages.push(self.page(&self.title, chunk, self.per_page, pages.len() + 1));
+        }
+        let heading = format!("{} · Solutions", self.title);
+        for chunk in solutions.chunks(self.solutions_per_page) {
+            pages.push(self.page(&heading, chunk, self.solutions_per_page, pages.len() + 1));
+        }
+
+        Ok(pages)
+    }
+
+    fn page(
+        &self,
+        heading: &str,
+        entries: &[(&Entry, Sudoku)],
+        per_page: usize,
+        number: usize,
+    ) -> String {
+        let (cols, side) = layout(per_page);
+        let rows = per_page.div_ceil(cols);
+        let slot_width = (PAGE_WIDTH - 2.0 * MARGIN) / cols as f64;
+        let slot_height = (PAGE_HEIGHT - 2.0 * MARGIN - HEADER - FOOTER) / rows as f64;
+        let font = escape(&self.options.font_family);
+        let view = render::size(&self.options);
+        let mut out = String::new();
+
+        let mut line = |text: String| {
+            out += &text;
+            out.push('\n');
+        };
+
+        line(format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}" viewBox="0 0 {PAGE_WIDTH} {PAGE_HEIGHT}">"#
+        ));
+        line(format!(
+            r#"<rect width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}" fill="white"/>"#
+        ));
+        line(format!(
+            r#"<text x="{:.1}" y="{:.1}" font-family="{font}" font-size="20" font-weight="bold" text-anchor="middle">{}</text>"#,
+            PAGE_WIDTH / 2.0,
+            MARGIN + 20.0,
+            escape(heading)
+        ));
+
+        for (i, (entry, filled)) in entries.iter().enumerate() {
+            let x = MARGIN + (i % cols) as f64 * slot_width + (slot_width - side) / 2.0;
+            let y = MARGIN
+                + HEADER
+                + (i / cols) as f64 * slot_height
+                + (slot_height + LABEL - side) / 2.0;
+
+            line(format!(
+                r#"<text x="{:.1}" y="{:.1}" font-family="{font}" font-size="12">{}</text>"#,
+                x + side / 19.0,
+                y - 4.0,
+                escape(&entry.label())
+            ));
+            line(format!(
+                r#"<svg x="{x:.1}" y="{y:.1}" width="{side:.1}" height="{side:.1}" viewBox="0 0 {view:.1} {view:.1}">"#
+            ));
+            line(render::contents(&entry.puzzle, filled, &self.options));
+            line("</svg>".to_owned());
+        }
+
+        line(format!(
+            r#"<text x="{:.1}" y="{:.1}" font-family="{font}" font-size="10" text-anchor="middle">{number}</text>"#,
+            PAGE_WIDTH / 2.0,
+            PAGE_HEIGHT - MARGIN
+        ));
+        line("</svg>".to_owned());
+
+        out
+    }
+
+    /// Writes the book as a PDF.
+    pub fn pdf(&self) -> Result<Vec<u8>, Error> {
+        let pages = self.pages()?;
+
+        let mut alloc = Ref::new(1);
+        let catalog_id = alloc.bump();
+        let tree_id = alloc.bump();
+        let page_ids: Vec<Ref> = pages.iter().map(|_| alloc.bump()).collect();
+        let name = Name(b"S1");
+        let mut pdf = Pdf::new();
+
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+
+        for (svg, page_id) in pages.iter().zip(&page_ids) {
+            let tree = render::parse(svg, &self.options)?;
+            let (chunk, svg_id) = svg2pdf::to_chunk(&tree, svg2pdf::ConversionOptions::default())
+                .map_err(|err| Error::Pdf {
+                msg: err.to_string(),
+            })?;
+
+            // the chunk numbers its objects from 1, like this document
+            let mut ids = HashMap::new();
+            let chunk = chunk.renumber(|old| *ids.entry(old).or_insert_with(|| alloc.bump()));
+            let svg_id = ids[&svg_id];
+            let content_id = alloc.bump();
+
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH as f32, PAGE_HEIGHT as f32));
+            page.parent(tree_id);
+            page.contents(content_id);
+            page.resources().x_objects().pair(name, svg_id);
+            page.finish();
+
+            let mut content = Content::new();
+            content
+                .transform([PAGE_WIDTH as f32, 0.0, 0.0, PAGE_HEIGHT as f32, 0.0, 0.0])
+                .x_object(name);
+            pdf.stream(content_id, &content.finish());
+            pdf.extend(&chunk);
+        }
+
+        Ok(pdf.finish())
+    }
+
+    /// Writes the book as a PDF file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, self.pdf()?).map_err(|err| Error::Io {
+            path: path.display().to_string(),
+            msg: err.to_string(),
+        })
+    }
+}
+
+#[pymethods]
+impl Book {
+    #[new]
+    #[args(per_page = "4", solutions_per_page = "6")]
+    fn py_new(title: &str, per_page: usize, solutions_per_page: usize) -> Self {
+        Self {
+            per_page,
+            solutions_per_page,
+            ..Self::new(title)
+        }
+    }
+
+    /// Adds a `Sudoku`, `Variant` or list of 81 cells.
+    #[pyo3(name = "add")]
+    #[args(title = "None", difficulty = "None")]
+    fn py_add(
+        &mut self,
+        puzzle: &PyAny,
+        title: Option<&str>,
+        difficulty: Option<&str>,
+    ) -> PyResult<()> {
+        self.add(render::extract_variant(puzzle)?, title, difficulty);
+
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The book as PDF bytes.
+    fn to_pdf<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        let bytes = py.allow_threads(|| self.pdf())?;
+
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    #[pyo3(name = "save")]
+    fn py_save(&self, py: Python<'_>, path: std::path::PathBuf) -> PyResult<()> {
+        Ok(py.allow_threads(|| self.save(&path))?)
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Canonical forms of puzzles under the symmetries of sudoku: relabeling the
+//! digits, transposing, permuting the rows of a band or the columns of a
+//! stack, and permuting the bands or the stacks. Two puzzles are equivalent
+//! exactly when their canonical forms are equal, so a corpus can be
+//! deduplicated by collecting canonical forms in a set.
+//!
+//! The canonical form is the arrangement whose rows, read in order, are
+//! smallest, with digits relabeled in the order they first appear and empty
+//! cells after every digit. It's found by choosing one row at a time and
+//! dropping arrangements as soon as a row is larger than the best so far.
+
+use crate::puzzle::Sudoku;
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+
+// sorts after every digit, so rows with more givens come first
+const EMPTY: usize = 10;
+
+type Grid = [[usize; 9]; 9];
+
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+// every order of the columns that keeps stacks together
+fn column_orders() -> impl Iterator<Item = [usize; 9]> {
+    let within = || PERMUTATIONS.iter();
+
+    PERMUTATIONS.iter().flat_map(move |stacks| {
+        within().flat_map(move |a| {
+            within().flat_map(move |b| {
+                within().map(move |c| {
+                    let mut order = [0; 9];
+                    for (j, column) in order.iter_mut().enumerate() {
+                        *column = stacks[j / 3] * 3 + [a, b, c][j / 3][j % 3];
+                    }
+                    order
+                })
+            })
+        })
+    })
+}
+
+// the first rows of an arrangement, and the labels given to digits so far
+#[derive(Copy, Clone)]
+struct Partial {
+    grid: usize,
+    columns: [usize; 9],
+    rows: [usize; 9],
+    labels: [usize; 10],
+    next_label: usize,
+}
+
+impl Partial {
+    // rows that can come after the first `depth`: any row of an unused band
+    // at the start of a band, otherwise the rest of the current band
+    fn choices(&self, depth: usize) -> impl Iterator<Item = usize> + '_ {
+        let used = &self.rows[..depth];
+
+        (0..9).filter(move |row| match depth % 3 {
+            0 => used.iter().all(|r| r / 3 != row / 3),
+            _ => row / 3 == used[depth - 1] / 3 && !used.contains(row),
+        })
+    }
+
+    fn label_row(&mut self, row: &[usize; 9]) -> [usize; 9] {
+        let mut key = [EMPTY; 9];
+
+        for (k, column) in key.iter_mut().zip(self.columns) {
+            let digit = row[column];
+            if digit == 0 {
+                continue;
+            }
+            if self.labels[digit] == 0 {
+                self.labels[digit] = self.next_label;
+                self.next_label += 1;
+            }
+            *k = self.labels[digit];
+        }
+
+        key
+    }
+}
+
+struct Search {
+    // the puzzle and its transpose
+    grids: [Grid; 2],
+    best: Grid,
+}
+
+impl Search {
+    fn extend(&mut self, partial: &Partial, depth: usize) {
+        if depth == 9 {
+            return;
+        }
+
+        for row in partial.choices(depth) {
+            let mut next = *partial;
+            next.rows[depth] = row;
+            let key = next.label_row(&self.grids[next.grid][row]);
+
+            match key.cmp(&self.best[depth]) {
+                Ordering::Greater => continue,
+                Ordering::Less => {
+                    self.best[depth] = key;
+                    for later in &mut self.best[depth + 1..] {
+                        *later = [EMPTY + 1; 9];
+                    }
+                }
+                Ordering::Equal => {}
+            }
+            self.extend(&next, depth + 1);
+        }
+    }
+}
+
+/// The canonical representative of every puzzle equivalent to `puzzle`.
+pub fn canonical_form(puzzle: &Sudoku) -> Sudoku {
+    let mut grids = [[[0; 9]; 9]; 2];
+    for (i, n) in puzzle.cells().iter().enumerate() {
+        grids[0][i / 9][i % 9] = *n;
+        grids[1][i % 9][i / 9] = *n;
+    }
+
+    let mut search = Search {
+        grids,
+        best: [[EMPTY + 1; 9]; 9],
+    };
+    for grid in 0..2 {
+        for columns in column_orders() {
+            let partial = Partial {
+                grid,
+                columns,
+                rows: [0; 9],
+                labels: [0; 10],
+                next_label: 1,
+            };
+            search.extend(&partial, 0);
+        }
+    }
+
+    let mut cells = [0; 81];
+    for (i, cell) in cells.iter_mut().enumerate() {
+        *cell = match search.best[i / 9][i % 9] {
+            EMPTY => 0,
+            n => n,
+        };
+    }
+
+    Sudoku::new(cells).expect("labels are digits")
+}
+
+/// Whether some symmetry of sudoku turns `a` into `b`.
+pub fn are_equivalent(a: &Sudoku, b: &Sudoku) -> bool {
+    a.cells().iter().filter(|n| **n != 0).count() == b.cells().iter().filter(|n| **n != 0).count()
+        && canonical_form(a) == canonical_form(b)
+}
+
+#[pyfunction]
+#[pyo3(name = "are_equivalent")]
+pub fn py_are_equivalent(py: Python<'_>, a: Sudoku, b: Sudoku) -> bool {
+    py.allow_threads(|| are_equivalent(&a, &b))
+}
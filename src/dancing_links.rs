@@ -12,6 +12,11 @@
 // either express or implied. See the License for the specific
 // language governing permissions and limitations under the License.
 
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct Point {
     x: usize,
@@ -77,9 +82,15 @@ impl std::fmt::Display for Node {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
 pub enum Error {
     InvalidMatrixSize { expected: usize, got: usize },
     InvalidPartialSolution { row: usize },
+    ConflictingPartialSolution { row: usize },
+    InvalidColumn { column: usize, width: usize },
+    DuplicateColumn { row: usize, column: usize },
+    InvalidColumnChoice { choice: usize, columns: usize },
     InternalError { msg: String },
     NoSolutions,
 }
@@ -99,6 +110,27 @@ impl std::fmt::Display for Error {
                     "invalid partial solution entered: row {row} is part of header!"
                 )
             }
+            Error::ConflictingPartialSolution { row } => {
+                write!(
+                    f,
+                    "invalid partial solution entered: row {row} overlaps an earlier row!"
+                )
+            }
+            Error::InvalidColumn { column, width } => {
+                write!(
+                    f,
+                    "invalid column: got {column}, matrix only has {width} columns!"
+                )
+            }
+            Error::DuplicateColumn { row, column } => {
+                write!(f, "invalid row: row {row} contains column {column} twice!")
+            }
+            Error::InvalidColumnChoice { choice, columns } => {
+                write!(
+                    f,
+                    "invalid column choice: chose {choice}, only {columns} columns remain!"
+                )
+            }
             Error::InternalError { msg } => {
                 write!(f, "internal error occurred: {msg}!")
             }
@@ -111,10 +143,199 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// An uncovered column, as seen by a [`ColumnChooser`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// index of the column in the original matrix
+    pub index: usize,
+    /// number of rows still covering the column
+    pub size: usize,
+}
+
+/// Strategy used to pick the column to branch on at each level of the search.
+///
+/// `columns` lists every remaining column from left to right, and the returned
+/// value is a position within that slice.
+pub trait ColumnChooser {
+    fn choose(&mut self, columns: &[Column]) -> usize;
+}
+
+impl<F: FnMut(&[Column]) -> usize> ColumnChooser for F {
+    fn choose(&mut self, columns: &[Column]) -> usize {
+        self(columns)
+    }
+}
+
+/// Always branches on the leftmost remaining column.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct FirstColumn;
+
+impl ColumnChooser for FirstColumn {
+    fn choose(&mut self, _columns: &[Column]) -> usize {
+        0
+    }
+}
+
+/// Branches on the column with the fewest rows (Knuth's S heuristic), taking
+/// the leftmost one on ties.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct MinimumRemainingValues;
+
+impl ColumnChooser for MinimumRemainingValues {
+    fn choose(&mut self, columns: &[Column]) -> usize {
+        let mut choice = 0;
+        let mut s = usize::MAX;
+
+        for (i, column) in columns.iter().enumerate() {
+            if column.size < s {
+                s = column.size;
+                choice = i;
+            }
+        }
+
+        choice
+    }
+}
+
+/// Branches on the column with the fewest rows, breaking ties at random.
+#[derive(Debug, Clone)]
+pub struct RandomMinimumRemainingValues {
+    rng: StdRng,
+}
+
+impl RandomMinimumRemainingValues {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ColumnChooser for RandomMinimumRemainingValues {
+    fn choose(&mut self, columns: &[Column]) -> usize {
+        let mut choice = 0;
+        let mut s = usize::MAX;
+        let mut ties = 0;
+
+        for (i, column) in columns.iter().enumerate() {
+            if column.size < s {
+                s = column.size;
+                choice = i;
+                ties = 1;
+            } else if column.size == s {
+                // reservoir sampling over tied columns
+                ties += 1;
+                if self.rng.gen_range(0..ties) == 0 {
+                    choice = i;
+                }
+            }
+        }
+
+        choice
+    }
+}
+
+/// Counters for a single level of the search tree.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelStats {
+    /// nodes visited at this depth
+    pub nodes: usize,
+    /// rows tried at this depth
+    pub branches: usize,
+}
+
+impl LevelStats {
+    /// Average number of rows tried per node at this depth.
+    pub fn branching_factor(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            self.branches as f64 / self.nodes as f64
+        }
+    }
+}
+
+/// Statistics collected while searching, see [`DancingLinks::solve_with_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// nodes of the search tree visited
+    pub nodes: usize,
+    /// link updates performed by `cover` and `uncover`
+    pub updates: usize,
+    /// deepest level reached, the root being level 0
+    pub max_depth: usize,
+    /// solutions found
+    pub solutions: usize,
+    /// per-depth counters, indexed by depth
+    pub levels: Vec<LevelStats>,
+}
+
+impl SearchStats {
+    fn visit(&mut self, depth: usize) {
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+
+        if self.levels.len() <= depth {
+            self.levels.resize(depth + 1, LevelStats::default());
+        }
+        self.levels[depth].nodes += 1;
+    }
+
+    fn branch(&mut self, depth: usize) {
+        self.levels[depth].branches += 1;
+    }
+}
+
+// vetoes rows given the row numbers chosen so far, see `solve_filtered`
+type Filter<'a> = &'a mut dyn FnMut(&[usize]) -> bool;
+
+// state threaded through a single search
+struct Search<'a, C: ?Sized> {
+    chooser: &'a mut C,
+    solutions: Vec<Vec<usize>>,
+    partial_solution: Vec<usize>,
+    stats: Option<SearchStats>,
+    rng: Option<StdRng>,
+    limit: Option<usize>,
+    count: usize,
+    count_only: bool,
+    filter: Option<Filter<'a>>,
+    row_numbers: Vec<usize>,
+    columns: Vec<Column>,
+}
+
+impl<'a, C: ColumnChooser + ?Sized> Search<'a, C> {
+    fn new(chooser: &'a mut C, stats: bool) -> Self {
+        Self {
+            chooser,
+            solutions: Vec::new(),
+            partial_solution: Vec::new(),
+            stats: stats.then(SearchStats::default),
+            rng: None,
+            limit: None,
+            count: 0,
+            count_only: false,
+            filter: None,
+            row_numbers: Vec::new(),
+            columns: Vec::new(),
+        }
+    }
+
+    fn finished(&self) -> bool {
+        match self.limit {
+            Some(limit) => self.count >= limit,
+            None => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DancingLinks {
     grid: Vec<Node>,
     width: usize,
+    primary: usize,
     height: usize,
 }
 
@@ -153,119 +374,177 @@ impl std::fmt::Display for DancingLinks {
 impl DancingLinks {
     pub fn new(matrix: &[bool], width: usize, height: usize) -> Result<Self, Error> {
         // check that dimensions are valid
-        if matrix.len() == width * height {
-            // count number of nodes
-            let num_ones: usize = matrix.iter().map(|b| if *b { 1 } else { 0 }).sum();
+        if matrix.len() != width * height {
+            return Err(Error::InvalidMatrixSize {
+                expected: width * height,
+                got: matrix.len(),
+            });
+        }
+
+        // convert each row into the indices of its set columns
+        let rows: Vec<Vec<usize>> = (0..height)
+            .map(|j| (0..width).filter(|i| matrix[j * width + i]).collect())
+            .collect();
 
-            // root + columns + nodes
-            let mut grid = Vec::with_capacity(1 + width + num_ones);
+        Self::from_rows(&rows, width, 0)
+    }
 
-            // create root node
-            let root_id = grid.len();
-            grid.push(Node::new(root_id, Data::Root));
+    /// Builds the matrix from a list of rows, each given as the indices of the
+    /// columns it covers. The first `primary` columns must be covered exactly
+    /// once, and the following `secondary` columns at most once.
+    pub fn from_rows(rows: &[Vec<usize>], primary: usize, secondary: usize) -> Result<Self, Error> {
+        let width = primary + secondary;
 
-            // create column nodes
-            let mut rcol_id = root_id;
-            for i in 0..width {
-                let col_id = grid.len();
-                grid.push(Node::new(col_id, Data::Size(0)));
+        // count number of nodes
+        let num_ones: usize = rows.iter().map(|row| row.len()).sum();
 
-                // knit into row
-                grid[root_id].l = col_id;
+        // root + columns + nodes
+        let mut grid = Vec::with_capacity(1 + width + num_ones);
+
+        // create root node
+        let root_id = grid.len();
+        grid.push(Node::new(root_id, Data::Root));
+
+        // create column nodes
+        for i in 0..width {
+            let col_id = grid.len();
+            grid.push(Node::new(col_id, Data::Size(0)));
+
+            // knit primary columns into row, secondary columns stay unlinked
+            if i < primary {
+                let l_root = grid[root_id].l;
+                grid[col_id].l = l_root;
                 grid[col_id].r = root_id;
-                grid[rcol_id].r = col_id;
-                grid[col_id].l = rcol_id;
-                rcol_id = col_id;
-
-                // create column
-                let mut trow_id = col_id;
-                for j in 0..height {
-                    // check if node should be created
-                    if matrix[j * width + i] {
-                        // create node at coordinate point
-                        let row_id = grid.len();
-                        grid.push(Node::new(row_id, Data::Point(Point { x: i, y: j })));
-
-                        // attach column id
-                        grid[row_id].c = col_id;
-
-                        // knit into column
-                        grid[col_id].u = row_id;
-                        grid[row_id].d = col_id;
-                        grid[trow_id].d = row_id;
-                        grid[row_id].u = trow_id;
-                        trow_id = row_id;
-
-                        // increase column size
-                        match grid[col_id].x {
-                            Data::Size(ref mut c) => *c += 1,
-                            _ => {
-                                return Err(Error::InternalError {
-                                    msg: "row object has invalid column header".to_string(),
-                                })
-                            }
-                        }
+                grid[l_root].r = col_id;
+                grid[root_id].l = col_id;
+            }
+        }
 
-                        // knit into start of row
-                        let mut row_start: Option<usize> = None;
-                        for (cell_id, cell) in grid.iter().enumerate() {
-                            if let Data::Point(point) = &cell.x {
-                                if point.y == j && point.x != i {
-                                    row_start = Some(cell_id);
-                                    break;
-                                }
-                            }
-                        }
-                        if let Some(row_start) = row_start {
-                            grid[row_start].l = row_id;
-                            grid[row_id].r = row_start;
-
-                            // knit into end of row
-                            let mut row_end: Option<usize> = None;
-                            for (cell_id, cell) in grid.iter().enumerate().rev() {
-                                if let Data::Point(point) = &cell.x {
-                                    if point.y == j && point.x != i && cell_id >= row_start {
-                                        row_end = Some(cell_id);
-                                        break;
-                                    }
-                                }
-                            }
-                            if let Some(row_end) = row_end {
-                                grid[row_end].r = row_id;
-                                grid[row_id].l = row_end;
-                            } else {
-                                return Err(Error::InternalError {
-                                    msg: "couldn't find end of row".to_owned(),
-                                });
-                            }
-                        }
+        for (j, row) in rows.iter().enumerate() {
+            let mut row_start: Option<usize> = None;
+
+            for &i in row {
+                if i >= width {
+                    return Err(Error::InvalidColumn { column: i, width });
+                }
+
+                let col_id = i + 1;
+
+                // a column can only appear once per row
+                if let Data::Point(p) = grid[grid[col_id].u].x {
+                    if p.y == j {
+                        return Err(Error::DuplicateColumn { row: j, column: i });
+                    }
+                }
+
+                // create node at coordinate point
+                let row_id = grid.len();
+                grid.push(Node::new(row_id, Data::Point(Point { x: i, y: j })));
+
+                // attach column id
+                grid[row_id].c = col_id;
+
+                // knit into bottom of column
+                let u_col = grid[col_id].u;
+                grid[row_id].u = u_col;
+                grid[row_id].d = col_id;
+                grid[u_col].d = row_id;
+                grid[col_id].u = row_id;
+
+                // increase column size
+                match grid[col_id].x {
+                    Data::Size(ref mut c) => *c += 1,
+                    _ => {
+                        return Err(Error::InternalError {
+                            msg: "row object has invalid column header".to_string(),
+                        })
+                    }
+                }
+
+                // knit into end of row
+                match row_start {
+                    Some(row_start) => {
+                        let row_end = grid[row_start].l;
+                        grid[row_id].l = row_end;
+                        grid[row_id].r = row_start;
+                        grid[row_end].r = row_id;
+                        grid[row_start].l = row_id;
                     }
+                    None => row_start = Some(row_id),
                 }
             }
+        }
 
-            if grid.len() == 1 + width + num_ones {
-                let dlx = DancingLinks {
-                    grid,
-                    width,
-                    height,
-                };
+        Ok(DancingLinks {
+            grid,
+            width,
+            primary,
+            height: rows.len(),
+        })
+    }
 
-                Ok(dlx)
-            } else {
-                Err(Error::InternalError {
-                    msg: "incorrect grid length".to_owned(),
-                })
+    /// Number of columns, primary and secondary.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of primary columns, which come before the secondary ones.
+    pub fn primary(&self) -> usize {
+        self.primary
+    }
+
+    /// The rows of the matrix as given to [`DancingLinks::from_rows`], each
+    /// with its columns in ascending order. Covering columns doesn't change
+    /// them.
+    pub fn rows(&self) -> Vec<Vec<usize>> {
+        let mut rows = vec![Vec::new(); self.height];
+
+        for node in &self.grid {
+            if let Data::Point(p) = node.x {
+                rows[p.y].push(p.x);
             }
-        } else {
-            Err(Error::InvalidMatrixSize {
-                expected: width * height,
-                got: matrix.len(),
-            })
         }
+        for row in &mut rows {
+            row.sort_unstable();
+        }
+
+        rows
+    }
+
+    /// Removes `column` and every row covering it from the matrix, returning
+    /// the number of link updates. This is the step the search repeats, only
+    /// exposed for benchmarks with the `bench` feature. Covers must be undone
+    /// with [`DancingLinks::uncover_column`] in the reverse order.
+    #[cfg(any(test, feature = "bench"))]
+    #[doc(hidden)]
+    pub fn cover_column(&mut self, column: usize) -> Result<usize, Error> {
+        if column >= self.width {
+            return Err(Error::InvalidColumn {
+                column,
+                width: self.width,
+            });
+        }
+
+        self.cover(column + 1)
     }
 
-    fn cover(&mut self, c: usize) -> Result<(), Error> {
+    /// Restores a column removed by [`DancingLinks::cover_column`].
+    #[cfg(any(test, feature = "bench"))]
+    #[doc(hidden)]
+    pub fn uncover_column(&mut self, column: usize) -> Result<usize, Error> {
+        if column >= self.width {
+            return Err(Error::InvalidColumn {
+                column,
+                width: self.width,
+            });
+        }
+
+        self.uncover(column + 1)
+    }
+
+    fn cover(&mut self, c: usize) -> Result<usize, Error> {
         let grid = &mut self.grid;
+        let mut updates = 1;
 
         // Step 1: Hide column header
         // L[R[c]] <- L[c]
@@ -290,6 +569,7 @@ impl DancingLinks {
                 let u_j = grid[j].u;
                 grid[d_j].u = u_j;
                 grid[u_j].d = d_j;
+                updates += 1;
 
                 // Step 5: Decrement column size
                 let c_j = grid[j].c;
@@ -308,11 +588,12 @@ impl DancingLinks {
             i = grid[i].d
         }
 
-        Ok(())
+        Ok(updates)
     }
 
-    fn uncover(&mut self, c: usize) -> Result<(), Error> {
+    fn uncover(&mut self, c: usize) -> Result<usize, Error> {
         let grid = &mut self.grid;
+        let mut updates = 1;
 
         // Step 1: Iterate through rows in column (upwards)
         let mut i = grid[c].u;
@@ -339,6 +620,7 @@ impl DancingLinks {
                 let u_j = grid[j].u;
                 grid[d_j].u = j;
                 grid[u_j].d = j;
+                updates += 1;
 
                 j = grid[j].l;
             }
@@ -354,11 +636,12 @@ impl DancingLinks {
         grid[r_c].l = c;
         grid[l_c].r = c;
 
-        Ok(())
+        Ok(updates)
     }
 
     fn partial_solve(&mut self, partial_solution: &[usize]) -> Result<Vec<usize>, Error> {
         let mut partial_solution_nodes = Vec::new();
+        let mut covered = vec![false; self.width + 1];
 
         for r in partial_solution {
             // convert row into id
@@ -379,14 +662,22 @@ impl DancingLinks {
                     });
                 }
 
+                // add row to solution
+                partial_solution_nodes.push(id);
+
                 // traverse columns rightwards
                 let mut i = id;
                 loop {
-                    // add node to solution
-                    partial_solution_nodes.push(i);
+                    let c = self.grid[i].c;
+
+                    // rows in a partial solution can't share columns
+                    if covered[c] {
+                        return Err(Error::ConflictingPartialSolution { row: *r });
+                    }
+                    covered[c] = true;
 
                     // cover column
-                    self.cover(self.grid[i].c)?;
+                    self.cover(c)?;
 
                     i = self.grid[i].r;
 
@@ -402,110 +693,795 @@ impl DancingLinks {
         Ok(partial_solution_nodes)
     }
 
-    fn search(
+    // `columns` is scratch space, reused so that choosing doesn't allocate
+    fn choose_column<C: ColumnChooser + ?Sized>(
+        &self,
+        chooser: &mut C,
+        columns: &mut Vec<Column>,
+    ) -> Result<usize, Error> {
+        // collect remaining columns
+        columns.clear();
+        let mut c = self.grid[0].r;
+        while c != 0 {
+            let size = match self.grid[c].x {
+                Data::Size(s) => s,
+                _ => {
+                    return Err(Error::InternalError {
+                        msg: "traversed non-column object while choosing column".to_string(),
+                    });
+                }
+            };
+
+            // column headers directly follow the root node
+            columns.push(Column { index: c - 1, size });
+
+            c = self.grid[c].r;
+        }
+
+        // let the strategy pick one of them
+        let choice = chooser.choose(columns);
+        match columns.get(choice) {
+            Some(column) => Ok(column.index + 1),
+            None => Err(Error::InvalidColumnChoice {
+                choice,
+                columns: columns.len(),
+            }),
+        }
+    }
+
+    fn cover_row(&mut self, r: usize) -> Result<usize, Error> {
+        let mut updates = 0;
+
+        // traverse columns rightwards
+        let mut j = self.grid[r].r;
+        while j != r {
+            // cover column j
+            updates += self.cover(self.grid[j].c)?;
+
+            j = self.grid[j].r;
+        }
+
+        Ok(updates)
+    }
+
+    fn uncover_row(&mut self, r: usize) -> Result<usize, Error> {
+        let mut updates = 0;
+
+        // traverse columns leftwards
+        let mut j = self.grid[r].l;
+        while j != r {
+            // uncover column j
+            updates += self.uncover(self.grid[j].c)?;
+
+            j = self.grid[j].l;
+        }
+
+        Ok(updates)
+    }
+
+    fn search<C: ColumnChooser + ?Sized>(
         &mut self,
-        k: usize,
-        solutions: &mut Vec<Vec<usize>>,
-        partial_solution: &mut Vec<usize>,
+        depth: usize,
+        state: &mut Search<'_, C>,
     ) -> Result<(), Error> {
+        if let Some(stats) = &mut state.stats {
+            stats.visit(depth);
+        }
+
         // If the matrix A has no columns, the current partial
         // solution is a valid solution; terminate successfully.
         if self.grid[0].r == 0 {
             // algorithm finished
-            solutions.push(partial_solution.clone());
+            state.count += 1;
+            if !state.count_only {
+                state.solutions.push(state.partial_solution.clone());
+            }
+
+            if let Some(stats) = &mut state.stats {
+                stats.solutions += 1;
+            }
         } else {
             // Otherwise choose a column c (deterministically).
-            let mut c = self.grid[0].r;
-            let mut s = usize::MAX;
-            let mut nc = c;
-            while nc != 0 {
-                let ns = match self.grid[c].x {
-                    Data::Size(s) => s,
-                    _ => {
-                        return Err(Error::InternalError {
-                            msg: "traversed non-column object while calculating minimum"
-                                .to_string(),
-                        });
-                    }
-                };
-
-                if ns < s {
-                    s = ns;
-                    c = nc;
-                }
+            let c = self.choose_column(state.chooser, &mut state.columns)?;
 
-                nc = self.grid[nc].r;
-            }
+            let mut updates = self.cover(c)?;
 
-            self.cover(c)?;
+            // only collect the rows up front when they need shuffling,
+            // otherwise walk down the column as it's restored
+            let mut shuffled = state.rng.as_mut().map(|rng| {
+                let mut rows = self.column_rows(c);
+                rows.shuffle(rng);
+                rows.into_iter()
+            });
 
             // Choose a row r such that Ar, c = 1 (nondeterministically).
-            let mut r = self.grid[c].d;
-            while r != c {
-                // add R to the partial solution
-                partial_solution.push(r);
+            let mut r = c;
+            loop {
+                r = match &mut shuffled {
+                    Some(rows) => match rows.next() {
+                        Some(r) => r,
+                        None => break,
+                    },
+                    None => self.grid[r].d,
+                };
+                if r == c {
+                    break;
+                }
 
-                // traverse columns rightwards
-                let mut j = self.grid[r].r;
-                while j != r {
-                    // cover column j
-                    self.cover(self.grid[j].c)?;
+                // let the caller veto rows breaking constraints of its own
+                if let Some(filter) = &mut state.filter {
+                    state.row_numbers.push(self.row_number(r)?);
+                    if !filter(&state.row_numbers) {
+                        state.row_numbers.pop();
+                        continue;
+                    }
+                }
 
-                    j = self.grid[j].r;
+                // add R to the partial solution
+                state.partial_solution.push(r);
+
+                if let Some(stats) = &mut state.stats {
+                    stats.branch(depth);
                 }
 
+                updates += self.cover_row(r)?;
+
                 // search again recursively
-                self.search(k + 1, solutions, partial_solution)?;
+                self.search(depth + 1, state)?;
 
                 // give up on solution
-                partial_solution.pop();
+                state.partial_solution.pop();
+                if state.filter.is_some() {
+                    state.row_numbers.pop();
+                }
 
-                j = self.grid[r].l;
-                while j != r {
-                    // uncover column j
-                    self.uncover(self.grid[j].c)?;
+                updates += self.uncover_row(r)?;
 
-                    j = self.grid[j].l;
+                if state.finished() {
+                    break;
                 }
+            }
 
-                r = self.grid[r].d;
+            updates += self.uncover(c)?;
+
+            if let Some(stats) = &mut state.stats {
+                stats.updates += updates;
             }
+        }
 
-            self.uncover(c)?;
+        Ok(())
+    }
+
+    fn run<C: ColumnChooser + ?Sized>(
+        &mut self,
+        partial_solution: Option<&[usize]>,
+        state: &mut Search<'_, C>,
+    ) -> Result<(), Error> {
+        state.partial_solution = match partial_solution {
+            Some(partial_solution) => self.partial_solve(partial_solution)?,
+            None => Vec::new(),
+        };
+        if state.filter.is_some() {
+            state.row_numbers = self.row_numbers(&state.partial_solution)?;
+        }
+
+        self.search(0, state)
+    }
+
+    fn column_rows(&self, c: usize) -> Vec<usize> {
+        let mut rows = Vec::new();
+
+        let mut r = self.grid[c].d;
+        while r != c {
+            rows.push(r);
+            r = self.grid[r].d;
+        }
+
+        rows
+    }
+
+    // converts a node id into the row number of the original matrix
+    fn row_number(&self, node: usize) -> Result<usize, Error> {
+        match self.grid[node].x {
+            Data::Point(p) => Ok(p.y),
+            _ => Err(Error::InternalError {
+                msg: "found non-row object in solution".to_owned(),
+            }),
+        }
+    }
+
+    fn row_numbers(&self, nodes: &[usize]) -> Result<Vec<usize>, Error> {
+        nodes.iter().map(|node| self.row_number(*node)).collect()
+    }
+
+    fn decode(&self, solutions: Vec<Vec<usize>>) -> Result<Vec<Vec<usize>>, Error> {
+        let solutions = solutions
+            .iter()
+            .map(|solution| self.row_numbers(solution))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if solutions.is_empty() {
+            Err(Error::NoSolutions)
+        } else {
+            Ok(solutions)
+        }
+    }
+
+    // collects the row choices leading to every subtree at `depth`, in search order
+    fn split<C: ColumnChooser + ?Sized>(
+        &mut self,
+        depth: usize,
+        chooser: &mut C,
+        prefix: &mut Vec<usize>,
+        tasks: &mut Vec<Vec<usize>>,
+    ) -> Result<(), Error> {
+        if depth == 0 || self.grid[0].r == 0 {
+            tasks.push(prefix.clone());
+            return Ok(());
+        }
+
+        let c = self.choose_column(chooser, &mut Vec::new())?;
+
+        self.cover(c)?;
+
+        let mut r = self.grid[c].d;
+        while r != c {
+            prefix.push(r);
+            self.cover_row(r)?;
+
+            self.split(depth - 1, chooser, prefix, tasks)?;
+
+            prefix.pop();
+            self.uncover_row(r)?;
+
+            r = self.grid[r].d;
         }
 
+        self.uncover(c)?;
+
         Ok(())
     }
 
-    pub fn solve(mut self, partial_solution: Option<&[usize]>) -> Result<Vec<Vec<usize>>, Error> {
+    // searches the subtree below `prefix`, then restores the matrix
+    fn run_task(
+        &mut self,
+        base: &[usize],
+        prefix: &[usize],
+        count_only: bool,
+    ) -> Result<(Vec<Vec<usize>>, usize), Error> {
+        for r in prefix {
+            self.cover(self.grid[*r].c)?;
+            self.cover_row(*r)?;
+        }
+
+        let mut chooser = MinimumRemainingValues;
+        let mut state = Search::new(&mut chooser, false);
+        state.count_only = count_only;
+        state.partial_solution = base.iter().chain(prefix).copied().collect();
+
+        self.search(prefix.len(), &mut state)?;
+
+        for r in prefix.iter().rev() {
+            self.uncover_row(*r)?;
+            self.uncover(self.grid[*r].c)?;
+        }
+
+        Ok((state.solutions, state.count))
+    }
+
+    fn run_parallel(
+        mut self,
+        partial_solution: Option<&[usize]>,
+        split_depth: usize,
+        threads: Option<usize>,
+        count_only: bool,
+    ) -> Result<(Vec<Vec<usize>>, usize), Error> {
+        let base = match partial_solution {
+            Some(partial_solution) => self.partial_solve(partial_solution)?,
+            None => Vec::new(),
+        };
+
+        let mut tasks = Vec::new();
+        self.split(
+            split_depth,
+            &mut MinimumRemainingValues,
+            &mut Vec::new(),
+            &mut tasks,
+        )?;
+
+        let threads = threads
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .clamp(1, tasks.len().max(1));
+
+        // workers pull tasks in order until none are left
+        let next = AtomicUsize::new(0);
+        let finished = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut dlx = self.clone();
+                        let mut finished = Vec::new();
+
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            match tasks.get(i) {
+                                Some(prefix) => {
+                                    finished.push((i, dlx.run_task(&base, prefix, count_only)))
+                                }
+                                None => break,
+                            }
+                        }
+
+                        finished
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join())
+                .collect::<Vec<_>>()
+        });
+
+        let mut results: Vec<_> = tasks.iter().map(|_| None).collect();
+        for worker in finished {
+            let worker = worker.map_err(|_| Error::InternalError {
+                msg: "search worker panicked".to_owned(),
+            })?;
+
+            for (i, result) in worker {
+                results[i] = Some(result);
+            }
+        }
+
+        // merge in task order so the output matches a sequential search
         let mut solutions = Vec::new();
+        let mut count = 0;
+        for result in results {
+            let (task_solutions, task_count) = result.ok_or_else(|| Error::InternalError {
+                msg: "search task was never run".to_owned(),
+            })??;
+
+            solutions.extend(task_solutions);
+            count += task_count;
+        }
+
+        if count_only {
+            Ok((solutions, count))
+        } else {
+            Ok((self.decode(solutions)?, count))
+        }
+    }
+
+    pub fn solve(self, partial_solution: Option<&[usize]>) -> Result<Vec<Vec<usize>>, Error> {
+        self.solve_with(partial_solution, &mut MinimumRemainingValues)
+    }
+
+    pub fn solve_with<C: ColumnChooser + ?Sized>(
+        mut self,
+        partial_solution: Option<&[usize]>,
+        chooser: &mut C,
+    ) -> Result<Vec<Vec<usize>>, Error> {
+        let mut state = Search::new(chooser, false);
+
+        self.run(partial_solution, &mut state)?;
+
+        self.decode(state.solutions)
+    }
 
-        let mut partial_solution = match partial_solution {
+    /// Like [`DancingLinks::solve_with`], but also collects [`SearchStats`],
+    /// stopping once `limit` solutions have been found.
+    pub fn solve_with_stats<C: ColumnChooser + ?Sized>(
+        mut self,
+        partial_solution: Option<&[usize]>,
+        limit: Option<usize>,
+        chooser: &mut C,
+    ) -> Result<(Vec<Vec<usize>>, SearchStats), Error> {
+        let mut state = Search::new(chooser, true);
+        state.limit = limit;
+
+        self.run(partial_solution, &mut state)?;
+
+        let solutions = self.decode(state.solutions)?;
+
+        Ok((solutions, state.stats.unwrap_or_default()))
+    }
+
+    /// Searches rows and tied columns in a random order determined by `seed`,
+    /// stopping once `limit` solutions have been found.
+    pub fn solve_random(
+        mut self,
+        partial_solution: Option<&[usize]>,
+        seed: u64,
+        limit: Option<usize>,
+    ) -> Result<Vec<Vec<usize>>, Error> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut chooser = RandomMinimumRemainingValues::new(rng.gen());
+
+        let mut state = Search::new(&mut chooser, false);
+        state.rng = Some(rng);
+        state.limit = limit;
+
+        self.run(partial_solution, &mut state)?;
+
+        self.decode(state.solutions)
+    }
+
+    /// Finds up to `limit` solutions, calling `filter` with the rows chosen so
+    /// far whenever a row is about to be added, and skipping the row if it
+    /// returns false. This prunes the search with constraints that can't be
+    /// written as exact cover.
+    pub fn solve_filtered(
+        mut self,
+        partial_solution: Option<&[usize]>,
+        limit: Option<usize>,
+        filter: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> Result<Vec<Vec<usize>>, Error> {
+        let mut chooser = MinimumRemainingValues;
+        let mut state = Search::new(&mut chooser, false);
+        state.filter = Some(filter);
+        state.limit = limit;
+
+        self.run(partial_solution, &mut state)?;
+
+        self.decode(state.solutions)
+    }
+
+    /// Counts solutions without storing them.
+    pub fn count(mut self, partial_solution: Option<&[usize]>) -> Result<usize, Error> {
+        let mut chooser = MinimumRemainingValues;
+        let mut state = Search::new(&mut chooser, false);
+        state.count_only = true;
+
+        self.run(partial_solution, &mut state)?;
+
+        Ok(state.count)
+    }
+
+    /// Splits the search tree at `split_depth` and searches the subtrees on
+    /// `threads` workers (all available cores by default). Solutions are
+    /// returned in the same order as [`DancingLinks::solve`].
+    pub fn solve_parallel(
+        self,
+        partial_solution: Option<&[usize]>,
+        split_depth: usize,
+        threads: Option<usize>,
+    ) -> Result<Vec<Vec<usize>>, Error> {
+        let (solutions, _) = self.run_parallel(partial_solution, split_depth, threads, false)?;
+
+        Ok(solutions)
+    }
+
+    /// Parallel version of [`DancingLinks::count`], see [`DancingLinks::solve_parallel`].
+    pub fn count_parallel(
+        self,
+        partial_solution: Option<&[usize]>,
+        split_depth: usize,
+        threads: Option<usize>,
+    ) -> Result<usize, Error> {
+        let (_, count) = self.run_parallel(partial_solution, split_depth, threads, true)?;
+
+        Ok(count)
+    }
+
+    /// Returns an iterator that finds solutions one at a time, in the same
+    /// order as [`DancingLinks::solve`].
+    pub fn into_solutions(
+        mut self,
+        partial_solution: Option<&[usize]>,
+    ) -> Result<Solutions, Error> {
+        let partial_solution = match partial_solution {
             Some(partial_solution) => self.partial_solve(partial_solution)?,
             None => Vec::new(),
         };
 
-        self.search(0, &mut solutions, &mut partial_solution)?;
+        Ok(Solutions {
+            dlx: self,
+            partial_solution,
+            stack: Vec::new(),
+            columns: Vec::new(),
+            started: false,
+            failed: false,
+        })
+    }
 
-        for solution in solutions.iter_mut() {
-            for node in solution.iter_mut() {
-                match self.grid[*node].x {
-                    Data::Point(p) => {
-                        *node = p.y;
-                    }
-                    _ => {
-                        return Err(Error::InternalError {
-                            msg: "found non-row object in solution".to_owned(),
-                        });
+    /// Returns an iterator over each step of the search, in the same order
+    /// as [`DancingLinks::into_solutions`]. The rows of `partial_solution`
+    /// are placed before the first event.
+    pub fn into_events(mut self, partial_solution: Option<&[usize]>) -> Result<Events, Error> {
+        let partial_solution = match partial_solution {
+            Some(partial_solution) => self.partial_solve(partial_solution)?,
+            None => Vec::new(),
+        };
+
+        Ok(Events {
+            dlx: self,
+            partial_solution,
+            stack: Vec::new(),
+            pending: VecDeque::new(),
+            columns: Vec::new(),
+            started: false,
+            failed: false,
+        })
+    }
+}
+
+// a level of the search, trying each row of `column` in turn
+#[derive(Debug, Clone)]
+struct Frame {
+    column: usize,
+    rows: Vec<usize>,
+    next: usize,
+    current: Option<usize>,
+}
+
+/// Lazy solution iterator, see [`DancingLinks::into_solutions`].
+#[derive(Debug, Clone)]
+pub struct Solutions {
+    dlx: DancingLinks,
+    partial_solution: Vec<usize>,
+    stack: Vec<Frame>,
+    columns: Vec<Column>,
+    started: bool,
+    failed: bool,
+}
+
+impl Solutions {
+    // opens a new level, or returns the current solution if no columns remain
+    fn descend(&mut self) -> Result<Option<Vec<usize>>, Error> {
+        if self.dlx.grid[0].r == 0 {
+            return self.dlx.row_numbers(&self.partial_solution).map(Some);
+        }
+
+        let c = self
+            .dlx
+            .choose_column(&mut MinimumRemainingValues, &mut self.columns)?;
+
+        self.dlx.cover(c)?;
+
+        self.stack.push(Frame {
+            column: c,
+            rows: self.dlx.column_rows(c),
+            next: 0,
+            current: None,
+        });
+
+        Ok(None)
+    }
+
+    fn advance(&mut self) -> Result<Option<Vec<usize>>, Error> {
+        if !self.started {
+            self.started = true;
+
+            if let Some(solution) = self.descend()? {
+                return Ok(Some(solution));
+            }
+        }
+
+        while let Some(frame) = self.stack.last_mut() {
+            // give up on the row tried last time
+            if let Some(r) = frame.current.take() {
+                self.partial_solution.pop();
+                self.dlx.uncover_row(r)?;
+            }
+
+            match frame.rows.get(frame.next).copied() {
+                Some(r) => {
+                    frame.next += 1;
+                    frame.current = Some(r);
+
+                    self.partial_solution.push(r);
+                    self.dlx.cover_row(r)?;
+
+                    if let Some(solution) = self.descend()? {
+                        return Ok(Some(solution));
                     }
                 }
+                None => {
+                    let c = frame.column;
+
+                    self.stack.pop();
+                    self.dlx.uncover(c)?;
+                }
             }
         }
 
-        if solutions.is_empty() {
-            Err(Error::NoSolutions)
-        } else {
-            Ok(solutions)
+        Ok(None)
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Result<Vec<usize>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.advance() {
+            Ok(solution) => solution.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A step of the search, as reported by [`Events`]. Columns and rows are
+/// numbered as in the original matrix, and `depth` counts the rows chosen
+/// by the search, not those of the partial solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Event {
+    /// `column` was picked to branch on, with `size` rows left to try
+    ChooseColumn {
+        column: usize,
+        size: usize,
+        depth: usize,
+    },
+    /// `row` was added to the partial solution
+    TryRow { row: usize, depth: usize },
+    /// `column` and the rows covering it were removed from the matrix
+    CoverColumn { column: usize },
+    /// `column` and the rows covering it were put back
+    UncoverColumn { column: usize },
+    /// `row` was taken out of the partial solution
+    Backtrack { row: usize, depth: usize },
+    /// no columns remain, so `rows` are a solution
+    Solution { rows: Vec<usize> },
+}
+
+/// Lazy iterator over the steps of the search, see
+/// [`DancingLinks::into_events`]. Each call to `next` does as little work
+/// as it can, so the search can be paused between any two events, or
+/// cloned to keep a copy of its state.
+#[derive(Debug, Clone)]
+pub struct Events {
+    dlx: DancingLinks,
+    partial_solution: Vec<usize>,
+    stack: Vec<Frame>,
+    pending: VecDeque<Event>,
+    columns: Vec<Column>,
+    started: bool,
+    failed: bool,
+}
+
+impl Events {
+    /// Rows of the current partial solution, starting with those given to
+    /// [`DancingLinks::into_events`].
+    pub fn rows(&self) -> Result<Vec<usize>, Error> {
+        self.dlx.row_numbers(&self.partial_solution)
+    }
+
+    // columns covered by a row, rightwards from the one after `r`
+    fn row_columns(&self, r: usize) -> Vec<usize> {
+        let mut columns = Vec::new();
+
+        let mut j = self.dlx.grid[r].r;
+        while j != r {
+            columns.push(self.dlx.grid[j].c);
+            j = self.dlx.grid[j].r;
+        }
+
+        columns
+    }
+
+    // opens a new level, or reports a solution if no columns remain
+    fn descend(&mut self) -> Result<(), Error> {
+        if self.dlx.grid[0].r == 0 {
+            let rows = self.rows()?;
+            self.pending.push_back(Event::Solution { rows });
+            return Ok(());
         }
+
+        let c = self
+            .dlx
+            .choose_column(&mut MinimumRemainingValues, &mut self.columns)?;
+        let size = match self.dlx.grid[c].x {
+            Data::Size(size) => size,
+            _ => {
+                return Err(Error::InternalError {
+                    msg: "chose non-column object".to_owned(),
+                })
+            }
+        };
+
+        self.pending.push_back(Event::ChooseColumn {
+            column: c - 1,
+            size,
+            depth: self.stack.len(),
+        });
+        self.dlx.cover(c)?;
+        self.pending.push_back(Event::CoverColumn { column: c - 1 });
+
+        self.stack.push(Frame {
+            column: c,
+            rows: self.dlx.column_rows(c),
+            next: 0,
+            current: None,
+        });
+
+        Ok(())
+    }
+
+    // takes one step of the search, returning false once it's over
+    fn step(&mut self) -> Result<bool, Error> {
+        if !self.started {
+            self.started = true;
+            self.descend()?;
+
+            return Ok(true);
+        }
+
+        let depth = self.stack.len().saturating_sub(1);
+        let frame = match self.stack.last_mut() {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
+
+        // give up on the row tried last time
+        if let Some(r) = frame.current.take() {
+            self.partial_solution.pop();
+            let row = self.dlx.row_number(r)?;
+            self.pending.push_back(Event::Backtrack { row, depth });
+
+            for c in self.row_columns(r).into_iter().rev() {
+                self.dlx.uncover(c)?;
+                self.pending
+                    .push_back(Event::UncoverColumn { column: c - 1 });
+            }
+
+            return Ok(true);
+        }
+
+        match frame.rows.get(frame.next).copied() {
+            Some(r) => {
+                frame.next += 1;
+                frame.current = Some(r);
+
+                self.partial_solution.push(r);
+                let row = self.dlx.row_number(r)?;
+                self.pending.push_back(Event::TryRow { row, depth });
+
+                for c in self.row_columns(r) {
+                    self.dlx.cover(c)?;
+                    self.pending.push_back(Event::CoverColumn { column: c - 1 });
+                }
+
+                self.descend()?;
+            }
+            None => {
+                let c = frame.column;
+
+                self.stack.pop();
+                self.dlx.uncover(c)?;
+                self.pending
+                    .push_back(Event::UncoverColumn { column: c - 1 });
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.failed {
+            match self.step() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
     }
 }
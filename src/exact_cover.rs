@@ -0,0 +1,302 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use crate::dancing_links::{DancingLinks, Error, Event, Events, Solutions};
+use crate::interop::{self, Dlx1};
+use crate::sudoku_alg;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyErr::new::<PyValueError, _>(err.to_string())
+    }
+}
+
+/// A general exact cover problem, solved with [`DancingLinks`].
+#[pyclass(module = "sudoku")]
+#[derive(Debug, Clone)]
+pub struct ExactCover {
+    dlx: DancingLinks,
+}
+
+impl ExactCover {
+    /// Creates a problem from rows given as column indices. Columns listed in
+    /// `secondary` may be covered at most once instead of exactly once, and
+    /// `columns` defaults to one more than the largest index used.
+    pub fn new(
+        rows: &[Vec<usize>],
+        columns: Option<usize>,
+        secondary: &[usize],
+    ) -> Result<Self, Error> {
+        let width = columns.unwrap_or_else(|| {
+            rows.iter()
+                .flatten()
+                .chain(secondary)
+                .map(|i| i + 1)
+                .max()
+                .unwrap_or(0)
+        });
+
+        if let Some(&column) = secondary.iter().find(|i| **i >= width) {
+            return Err(Error::InvalidColumn { column, width });
+        }
+
+        // DancingLinks expects primary columns first, then secondary ones
+        let mut is_secondary = vec![false; width];
+        for i in secondary {
+            is_secondary[*i] = true;
+        }
+        let order = (0..width)
+            .filter(|i| !is_secondary[*i])
+            .chain((0..width).filter(|i| is_secondary[*i]));
+        let mut mapping = vec![0; width];
+        for (new, old) in order.enumerate() {
+            mapping[old] = new;
+        }
+
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|i| match mapping.get(*i) {
+                        Some(new) => Ok(*new),
+                        None => Err(Error::InvalidColumn { column: *i, width }),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<usize>>, Error>>()?;
+
+        let secondary = is_secondary.iter().filter(|s| **s).count();
+        let dlx = DancingLinks::from_rows(&rows, width - secondary, secondary)?;
+
+        Ok(Self { dlx })
+    }
+
+    /// Reads a problem in Knuth's DLX1 format, with a row for each option.
+    /// Item names are dropped, so columns are numbered in the order the
+    /// items are listed, primary items first.
+    pub fn from_dlx1(text: &str) -> Result<Self, interop::Error> {
+        Ok(Self {
+            dlx: Dlx1::parse(text)?.to_dancing_links()?,
+        })
+    }
+
+    /// Writes the problem in DLX1 format, see [`Dlx1::from_dancing_links`].
+    pub fn to_dlx1(&self) -> String {
+        Dlx1::from_dancing_links(&self.dlx).to_string()
+    }
+
+    /// Finds up to `limit` solutions containing every row of `partial`.
+    pub fn solve(
+        &self,
+        limit: Option<usize>,
+        partial: Option<&[usize]>,
+    ) -> Result<Vec<Vec<usize>>, Error> {
+        self.solutions(partial)?
+            .take(limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    pub fn solutions(&self, partial: Option<&[usize]>) -> Result<Solutions, Error> {
+        self.dlx.clone().into_solutions(partial)
+    }
+
+    /// Steps of the search, see [`DancingLinks::into_events`]. Columns in
+    /// events are numbered with the primary columns first, then the
+    /// secondary ones.
+    pub fn events(&self, partial: Option<&[usize]>) -> Result<Events, Error> {
+        self.dlx.clone().into_events(partial)
+    }
+
+    pub fn count(
+        &self,
+        partial: Option<&[usize]>,
+        split_depth: usize,
+        threads: Option<usize>,
+    ) -> Result<usize, Error> {
+        self.dlx
+            .clone()
+            .count_parallel(partial, split_depth, threads)
+    }
+}
+
+#[pymethods]
+impl ExactCover {
+    #[new]
+    #[args(columns = "None", secondary = "None")]
+    fn py_new(
+        rows: Vec<Vec<usize>>,
+        columns: Option<usize>,
+        secondary: Option<Vec<usize>>,
+    ) -> PyResult<Self> {
+        Ok(Self::new(&rows, columns, &secondary.unwrap_or_default())?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_dlx1")]
+    fn py_from_dlx1(text: &str) -> PyResult<Self> {
+        Ok(Self::from_dlx1(text)?)
+    }
+
+    #[pyo3(name = "to_dlx1")]
+    fn py_to_dlx1(&self) -> String {
+        self.to_dlx1()
+    }
+
+    #[pyo3(name = "solve")]
+    #[args(limit = "None", partial = "None")]
+    fn py_solve(
+        &self,
+        py: Python<'_>,
+        limit: Option<usize>,
+        partial: Option<Vec<usize>>,
+    ) -> PyResult<Vec<Vec<usize>>> {
+        Ok(py.allow_threads(|| self.solve(limit, partial.as_deref()))?)
+    }
+
+    #[pyo3(name = "solutions")]
+    #[args(partial = "None")]
+    fn py_solutions(&self, partial: Option<Vec<usize>>) -> PyResult<SolutionIter> {
+        Ok(SolutionIter {
+            inner: self.solutions(partial.as_deref())?,
+        })
+    }
+
+    #[pyo3(name = "events")]
+    #[args(partial = "None")]
+    fn py_events(&self, partial: Option<Vec<usize>>) -> PyResult<EventIter> {
+        Ok(EventIter::new(self.events(partial.as_deref())?, false))
+    }
+
+    fn __iter__(&self) -> PyResult<SolutionIter> {
+        self.py_solutions(None)
+    }
+
+    #[pyo3(name = "count")]
+    #[args(partial = "None", split_depth = "2", threads = "None")]
+    fn py_count(
+        &self,
+        py: Python<'_>,
+        partial: Option<Vec<usize>>,
+        split_depth: usize,
+        threads: Option<usize>,
+    ) -> PyResult<usize> {
+        Ok(py.allow_threads(|| self.count(partial.as_deref(), split_depth, threads))?)
+    }
+}
+
+/// Python iterator over the solutions of an [`ExactCover`].
+#[pyclass(module = "sudoku")]
+pub struct SolutionIter {
+    inner: Solutions,
+}
+
+#[pymethods]
+impl SolutionIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Vec<usize>>> {
+        let inner = &mut self.inner;
+
+        Ok(py.allow_threads(|| inner.next()).transpose()?)
+    }
+}
+
+/// Python iterator over the steps of a search, yielding each [`Event`] as a
+/// dict with its `kind` in snake case alongside its fields.
+#[pyclass(module = "sudoku")]
+pub struct EventIter {
+    inner: Events,
+    // add sudoku placements to rows, see `sudoku_alg::search_events`
+    sudoku: bool,
+}
+
+impl EventIter {
+    pub fn new(inner: Events, sudoku: bool) -> Self {
+        Self { inner, sudoku }
+    }
+
+    fn to_dict(&self, py: Python<'_>, event: Event) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        let row_placement = |row: usize| -> PyResult<()> {
+            if self.sudoku {
+                let (r, c, digit) = sudoku_alg::decode_row(row);
+                dict.set_item("cell", (r, c))?;
+                dict.set_item("digit", digit)?;
+            }
+            dict.set_item("row", row)
+        };
+
+        match event {
+            Event::ChooseColumn {
+                column,
+                size,
+                depth,
+            } => {
+                dict.set_item("kind", "choose_column")?;
+                dict.set_item("column", column)?;
+                dict.set_item("size", size)?;
+                dict.set_item("depth", depth)?;
+            }
+            Event::TryRow { row, depth } => {
+                dict.set_item("kind", "try_row")?;
+                row_placement(row)?;
+                dict.set_item("depth", depth)?;
+            }
+            Event::CoverColumn { column } => {
+                dict.set_item("kind", "cover_column")?;
+                dict.set_item("column", column)?;
+            }
+            Event::UncoverColumn { column } => {
+                dict.set_item("kind", "uncover_column")?;
+                dict.set_item("column", column)?;
+            }
+            Event::Backtrack { row, depth } => {
+                dict.set_item("kind", "backtrack")?;
+                row_placement(row)?;
+                dict.set_item("depth", depth)?;
+            }
+            Event::Solution { rows } => {
+                dict.set_item("kind", "solution")?;
+                if self.sudoku {
+                    dict.set_item("grid", sudoku_alg::decode_solution(&rows).to_vec())?;
+                }
+                dict.set_item("rows", rows)?;
+            }
+        }
+
+        Ok(dict.into())
+    }
+}
+
+#[pymethods]
+impl EventIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        match self.inner.next().transpose()? {
+            Some(event) => Ok(Some(self.to_dict(py, event)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Rows of the current partial solution.
+    fn rows(&self) -> PyResult<Vec<usize>> {
+        Ok(self.inner.rows()?)
+    }
+}
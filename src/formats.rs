@@ -0,0 +1,659 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Puzzle files saved by desktop solvers: SudoCue `.sdk`, Simple Sudoku `.ss`
+//! and SadMan Software `.sdx`.
+//!
+//! `.sdk` and `.ss` files hold the givens as a 9x9 grid of digits and `.`,
+//! optionally followed by a pencil-mark grid of 9 rows of 9 candidate lists.
+//! In the pencil-mark grid, a lone digit in a cell without a given is read as
+//! a digit placed by the solver. `.sdx` files hold one row per line, with
+//! givens written as a lone digit, placed digits prefixed with `u` and every
+//! other cell written as its list of candidates.
+//!
+//! None of the formats can tell a cell with a single candidate apart from a
+//! placed digit, so such cells read back as placed digits.
+
+use crate::puzzle::Sudoku;
+use pyo3::{
+    basic::CompareOp,
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        got: char,
+    },
+    InvalidRowLength {
+        line: usize,
+        got: usize,
+    },
+    MissingRows {
+        line: usize,
+        got: usize,
+        expected: usize,
+    },
+    UnexpectedLine {
+        line: usize,
+    },
+    UnknownFormat {
+        got: String,
+    },
+    Io {
+        path: String,
+        msg: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidCharacter { line, column, got } => {
+                write!(
+                    f,
+                    "invalid character {got:?} at line {line}, column {column}!"
+                )
+            }
+            Error::InvalidRowLength { line, got } => {
+                write!(
+                    f,
+                    "invalid row at line {line}: got {got} cells, expected 9!"
+                )
+            }
+            Error::MissingRows {
+                line,
+                got,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "unexpected end of grid at line {line}: got {got} rows, expected {expected}!"
+                )
+            }
+            Error::UnexpectedLine { line } => {
+                write!(f, "unexpected content after the grid at line {line}!")
+            }
+            Error::UnknownFormat { got } => {
+                write!(f, "unknown puzzle file format: got {got:?}!")
+            }
+            Error::Io { path, msg } => write!(f, "can't access {path:?}: {msg}!"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io { .. } => PyErr::new::<PyOSError, _>(err.to_string()),
+            _ => PyErr::new::<PyValueError, _>(err.to_string()),
+        }
+    }
+}
+
+/// Supported puzzle file formats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Format {
+    /// SudoCue `.sdk`
+    Sdk,
+    /// Simple Sudoku `.ss`
+    Ss,
+    /// SadMan Software `.sdx`
+    Sdx,
+}
+
+impl Format {
+    /// Guesses the format from a file extension.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+        extension.to_ascii_lowercase().parse()
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sdk" => Ok(Format::Sdk),
+            "ss" => Ok(Format::Ss),
+            "sdx" => Ok(Format::Sdx),
+            _ => Err(Error::UnknownFormat { got: s.to_owned() }),
+        }
+    }
+}
+
+/// The contents of a puzzle file: the givens, the progress made on top of
+/// them and, if saved, the pencil marks of every cell.
+#[pyclass(module = "sudoku")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PuzzleFile {
+    #[pyo3(get, set)]
+    pub givens: Sudoku,
+    /// digits placed by the solver, 0 for givens and empty cells
+    #[pyo3(get, set)]
+    pub placed: Sudoku,
+    /// candidates of each cell, row by row; filled cells have none
+    pub candidates: Option<Vec<Vec<usize>>>,
+    /// `#` header lines of `.sdk` files, without the leading `#`
+    #[pyo3(get, set)]
+    pub comments: Vec<String>,
+}
+
+impl PuzzleFile {
+    pub fn new(givens: Sudoku) -> Self {
+        Self {
+            givens,
+            placed: Sudoku::default(),
+            candidates: None,
+            comments: Vec::new(),
+        }
+    }
+
+    /// The givens together with the placed digits.
+    pub fn current(&self) -> Sudoku {
+        let mut cells = *self.givens.cells();
+        for (cell, n) in cells.iter_mut().zip(self.placed.cells()) {
+            if *cell == 0 {
+                *cell = *n;
+            }
+        }
+
+        Sudoku::new(cells).expect("givens and placed digits are valid cells")
+    }
+}
+
+#[pymethods]
+impl PuzzleFile {
+    #[new]
+    #[args(placed = "None", candidates = "None", comments = "None")]
+    fn py_new(
+        givens: Sudoku,
+        placed: Option<Sudoku>,
+        candidates: Option<Vec<Vec<Vec<usize>>>>,
+        comments: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let mut file = Self {
+            placed: placed.unwrap_or_default(),
+            comments: comments.unwrap_or_default(),
+            ..Self::new(givens)
+        };
+        file.set_candidates(candidates)?;
+
+        Ok(file)
+    }
+
+    /// Candidates as a 9x9 grid of lists, like `Sudoku.candidates`.
+    #[getter]
+    fn get_candidates(&self) -> Option<Vec<Vec<Vec<usize>>>> {
+        let candidates = self.candidates.as_ref()?;
+
+        Some(candidates.chunks(9).map(<[_]>::to_vec).collect())
+    }
+
+    #[setter]
+    fn set_candidates(&mut self, candidates: Option<Vec<Vec<Vec<usize>>>>) -> PyResult<()> {
+        if let Some(rows) = &candidates {
+            if rows.len() != 9 || rows.iter().any(|row| row.len() != 9) {
+                return Err(PyValueError::new_err(
+                    "candidates must be a 9x9 grid of lists!",
+                ));
+            }
+            if rows
+                .iter()
+                .flatten()
+                .flatten()
+                .any(|d| !(1..=9).contains(d))
+            {
+                return Err(PyValueError::new_err(
+                    "candidates must be digits between 1 and 9!",
+                ));
+            }
+        }
+        self.candidates = candidates.map(|rows| rows.concat());
+
+        Ok(())
+    }
+
+    #[pyo3(name = "current")]
+    fn py_current(&self) -> Sudoku {
+        self.current()
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyObject {
+        match op {
+            CompareOp::Eq => (self == other).into_py(py),
+            CompareOp::Ne => (self != other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+}
+
+/// Reads a puzzle file's contents.
+pub fn read(text: &str, format: Format) -> Result<PuzzleFile, Error> {
+    match format {
+        Format::Sdk | Format::Ss => read_grids(text),
+        Format::Sdx => read_sdx(text),
+    }
+}
+
+/// Writes a puzzle file's contents. `.ss` files have no room for comments, so
+/// those are dropped. Placed digits are saved in the pencil-mark grid, which
+/// is filled in from the current grid if the file has no candidates.
+pub fn write(file: &PuzzleFile, format: Format) -> String {
+    match format {
+        Format::Sdk | Format::Ss => write_grids(file, format),
+        Format::Sdx => write_sdx(file),
+    }
+}
+
+/// Reads a puzzle file, using its extension to pick the format.
+pub fn load(path: &Path) -> Result<PuzzleFile, Error> {
+    let format = Format::from_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(|err| io_error(path, err))?;
+
+    read(&text, format)
+}
+
+/// Writes a puzzle file, using its extension to pick the format.
+pub fn save(file: &PuzzleFile, path: &Path) -> Result<(), Error> {
+    let format = Format::from_path(path)?;
+
+    std::fs::write(path, write(file, format)).map_err(|err| io_error(path, err))
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Io {
+        path: path.display().to_string(),
+        msg: err.to_string(),
+    }
+}
+
+// decoration lines made of `-`, `+`, `*` and `|`
+fn is_rule(row: &str) -> bool {
+    row.contains('-')
+        && row
+            .chars()
+            .all(|ch| ch.is_whitespace() || "-+*|".contains(ch))
+}
+
+// the tokens of a row split on whitespace and `|`, with their 1-based column
+fn tokens(row: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (column, (i, ch)) in row.char_indices().enumerate() {
+        let separator = ch.is_whitespace() || ch == '|';
+        match start {
+            Some((j, col)) if separator => {
+                tokens.push((col, &row[j..i]));
+                start = None;
+            }
+            None if !separator => start = Some((i, column + 1)),
+            _ => {}
+        }
+    }
+    if let Some((j, col)) = start {
+        tokens.push((col, &row[j..]));
+    }
+
+    tokens
+}
+
+fn digits(token: &str, line: usize, column: usize) -> Result<Vec<usize>, Error> {
+    token
+        .chars()
+        .enumerate()
+        .map(|(i, ch)| match ch {
+            '1'..='9' => Ok(ch as usize - '0' as usize),
+            _ => Err(Error::InvalidCharacter {
+                line,
+                column: column + i,
+                got: ch,
+            }),
+        })
+        .collect()
+}
+
+fn read_grids(text: &str) -> Result<PuzzleFile, Error> {
+    let mut givens = Vec::with_capacity(81);
+    let mut marks = Vec::with_capacity(81);
+    let mut comments = Vec::new();
+    let mut rows = 0;
+    let mut last = 0;
+
+    for (line, row) in text.lines().enumerate() {
+        let line = line + 1;
+        last = line;
+
+        if let Some(comment) = row.strip_prefix('#') {
+            comments.push(comment.to_owned());
+            continue;
+        }
+        if row.trim().is_empty() || is_rule(row) {
+            continue;
+        }
+
+        if rows < 9 {
+            // givens, one character per cell
+            let mut cells = Vec::with_capacity(9);
+            for (column, ch) in row.chars().enumerate() {
+                match ch {
+                    '.' => cells.push(0),
+                    '0'..='9' => cells.push(ch as usize - '0' as usize),
+                    '|' => {}
+                    _ if ch.is_whitespace() => {}
+                    _ => {
+                        return Err(Error::InvalidCharacter {
+                            line,
+                            column: column + 1,
+                            got: ch,
+                        })
+                    }
+                }
+            }
+            if cells.len() != 9 {
+                return Err(Error::InvalidRowLength {
+                    line,
+                    got: cells.len(),
+                });
+            }
+            givens.extend(cells);
+        } else if rows < 18 {
+            // pencil marks, one list of candidates per cell
+            let cells = tokens(row);
+            if cells.len() != 9 {
+                return Err(Error::InvalidRowLength {
+                    line,
+                    got: cells.len(),
+                });
+            }
+            for (column, token) in cells {
+                marks.push(digits(token, line, column)?);
+            }
+        } else {
+            return Err(Error::UnexpectedLine { line });
+        }
+        rows += 1;
+    }
+
+    if rows != 9 && rows != 18 {
+        return Err(Error::MissingRows {
+            line: last + 1,
+            got: rows,
+            // pencil marks follow the givens
+            expected: if rows < 9 { 9 } else { 18 },
+        });
+    }
+
+    let givens = Sudoku::from_slice(&givens).expect("cells are digits and there are 81 of them");
+    let mut file = PuzzleFile::new(givens);
+    file.comments = comments;
+
+    if !marks.is_empty() {
+        let mut placed = [0; 81];
+        for (i, cell) in marks.iter_mut().enumerate() {
+            if givens.cells()[i] != 0 {
+                cell.clear();
+            } else if cell.len() == 1 {
+                placed[i] = cell.pop().expect("cell has one candidate");
+            }
+        }
+        file.placed = Sudoku::new(placed).expect("placed digits are between 1 and 9");
+        file.candidates = Some(marks);
+    }
+
+    Ok(file)
+}
+
+fn read_sdx(text: &str) -> Result<PuzzleFile, Error> {
+    let mut givens = [0; 81];
+    let mut placed = [0; 81];
+    let mut candidates = vec![Vec::new(); 81];
+    let mut rows = 0;
+    let mut last = 0;
+
+    for (line, row) in text.lines().enumerate() {
+        let line = line + 1;
+        last = line;
+
+        if row.trim().is_empty() {
+            continue;
+        }
+        if rows == 9 {
+            return Err(Error::UnexpectedLine { line });
+        }
+
+        let cells = tokens(row);
+        if cells.len() != 9 {
+            return Err(Error::InvalidRowLength {
+                line,
+                got: cells.len(),
+            });
+        }
+
+        for (col, (column, token)) in cells.into_iter().enumerate() {
+            let i = rows * 9 + col;
+
+            if let Some(digit) = token.strip_prefix('u') {
+                match digits(digit, line, column + 1)?[..] {
+                    [n] => placed[i] = n,
+                    // `u` must be followed by exactly one digit
+                    _ => {
+                        return Err(Error::InvalidCharacter {
+                            line,
+                            column,
+                            got: 'u',
+                        })
+                    }
+                }
+            } else if token == "." {
+                // an empty cell without candidates
+            } else {
+                match digits(token, line, column)?[..] {
+                    [n] => givens[i] = n,
+                    ref marks => candidates[i] = marks.to_vec(),
+                }
+            }
+        }
+        rows += 1;
+    }
+
+    if rows != 9 {
+        return Err(Error::MissingRows {
+            line: last + 1,
+            got: rows,
+            expected: 9,
+        });
+    }
+
+    let mut file = PuzzleFile::new(Sudoku::new(givens).expect("givens are between 1 and 9"));
+    file.placed = Sudoku::new(placed).expect("placed digits are between 1 and 9");
+    file.candidates = Some(candidates);
+
+    Ok(file)
+}
+
+// candidates to save for each cell, with filled cells written as their digit
+fn marks(file: &PuzzleFile) -> Vec<String> {
+    let current = file.current();
+    let candidates = match &file.candidates {
+        Some(candidates) => candidates.clone(),
+        None => current.candidates(),
+    };
+
+    current
+        .cells()
+        .iter()
+        .enumerate()
+        .map(|(i, n)| match n {
+            0 => candidates
+                .get(i)
+                .map(|cell| cell.iter().map(|d| d.to_string()).collect())
+                .unwrap_or_default(),
+            n => n.to_string(),
+        })
+        .collect()
+}
+
+fn write_grids(file: &PuzzleFile, format: Format) -> String {
+    let mut out = String::new();
+    let cells = file.givens.cells();
+    let digit = |n: usize| match n {
+        0 => '.',
+        n => char::from(b'0' + n as u8),
+    };
+
+    if format == Format::Sdk {
+        for comment in &file.comments {
+            out += &format!("#{comment}\n");
+        }
+        for row in cells.chunks(9) {
+            out.extend(row.iter().map(|n| digit(*n)));
+            out.push('\n');
+        }
+    } else {
+        out += "*-----------*\n";
+        for (r, row) in cells.chunks(9).enumerate() {
+            if r == 3 || r == 6 {
+                out += "|---+---+---|\n";
+            }
+            for stack in row.chunks(3) {
+                out.push('|');
+                out.extend(stack.iter().map(|n| digit(*n)));
+            }
+            out += "|\n";
+        }
+        out += "*-----------*\n";
+    }
+
+    // cells without candidates, like `.` in sdx, can't be written in a grid,
+    // so they get the candidates left by the digits instead
+    let current = file.current().candidates();
+    let mut marks = marks(file);
+    for (mark, candidates) in marks.iter_mut().zip(current) {
+        if mark.is_empty() {
+            *mark = candidates.iter().map(|d| d.to_string()).collect();
+        }
+    }
+
+    // a cell with no candidates left would still be unreadable
+    let has_placed = file.placed.cells().iter().any(|n| *n != 0);
+    if (file.candidates.is_some() || has_placed) && marks.iter().all(|m| !m.is_empty()) {
+        out.push('\n');
+        out += &write_marks(&marks);
+    }
+
+    out
+}
+
+fn write_marks(marks: &[String]) -> String {
+    let widths: Vec<usize> = (0..9)
+        .map(|c| (0..9).map(|r| marks[r * 9 + c].len()).max().unwrap_or(0))
+        .collect();
+    let rule: Vec<String> = widths
+        .chunks(3)
+        .map(|stack| "-".repeat(stack.iter().map(|w| w + 2).sum::<usize>() + 1))
+        .collect();
+    let rule = format!("+{}+\n", rule.join("+"));
+
+    let mut out = rule.clone();
+    for r in 0..9 {
+        if r == 3 || r == 6 {
+            out += &rule;
+        }
+        for c in 0..9 {
+            if c % 3 == 0 {
+                out += "| ";
+            }
+            out += &format!("{:<width$}  ", marks[r * 9 + c], width = widths[c]);
+        }
+        out += "|\n";
+    }
+    out += &rule;
+
+    out
+}
+
+fn write_sdx(file: &PuzzleFile) -> String {
+    let marks = marks(file);
+    let mut out = String::new();
+
+    for r in 0..9 {
+        let row: Vec<String> = (0..9)
+            .map(|c| {
+                let i = r * 9 + c;
+                match (file.givens.cells()[i], file.placed.cells()[i]) {
+                    (0, 0) if marks[i].is_empty() => ".".to_owned(),
+                    (0, 0) if marks[i].len() == 1 => format!("u{}", marks[i]),
+                    (0, 0) => marks[i].clone(),
+                    (0, n) => format!("u{n}"),
+                    (n, _) => n.to_string(),
+                }
+            })
+            .collect();
+        out += &row.join(" ");
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Reads a `.sdk`, `.ss` or `.sdx` puzzle file, picking the format from the
+/// extension unless `format` is given.
+#[pyfunction(format = "None")]
+pub fn read_file(py: Python<'_>, path: &str, format: Option<&str>) -> PyResult<PuzzleFile> {
+    let format = match format {
+        Some(format) => format.parse()?,
+        None => Format::from_path(Path::new(path))?,
+    };
+    let file = py.allow_threads(|| {
+        let text = std::fs::read_to_string(path).map_err(|err| io_error(Path::new(path), err))?;
+        read(&text, format)
+    })?;
+
+    Ok(file)
+}
+
+/// Writes a `.sdk`, `.ss` or `.sdx` puzzle file, picking the format from the
+/// extension unless `format` is given.
+#[pyfunction(format = "None")]
+pub fn write_file(
+    py: Python<'_>,
+    file: PuzzleFile,
+    path: &str,
+    format: Option<&str>,
+) -> PyResult<()> {
+    let format = match format {
+        Some(format) => format.parse()?,
+        None => Format::from_path(Path::new(path))?,
+    };
+    py.allow_threads(|| {
+        std::fs::write(path, write(&file, format)).map_err(|err| io_error(Path::new(path), err))
+    })?;
+
+    Ok(())
+}
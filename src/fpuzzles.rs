@@ -0,0 +1,331 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Imports variant puzzles shared as f-puzzles JSON, either as is or
+//! LZ-string compressed the way f-puzzles and SudokuPad put them in URLs.
+
+use crate::puzzle::Sudoku;
+use crate::variant::{Arrow, Cage, Dot, DotKind, Variant};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    InvalidPayload,
+    InvalidJson { msg: String },
+    InvalidCell { got: String },
+    InvalidSize { got: usize },
+    Unsupported { constraints: Vec<String> },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidPayload => {
+                write!(f, "payload is neither JSON nor LZ-string compressed JSON!")
+            }
+            Error::InvalidJson { msg } => write!(f, "invalid f-puzzles JSON: {msg}!"),
+            Error::InvalidCell { got } => write!(f, "invalid cell reference: got {got:?}!"),
+            Error::InvalidSize { got } => {
+                write!(f, "unsupported grid size: got {got}, expected 9!")
+            }
+            Error::Unsupported { constraints } => {
+                write!(f, "unsupported constraints: {}!", constraints.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyErr::new::<PyValueError, _>(err.to_string())
+    }
+}
+
+// keys that don't constrain the solution
+const IGNORED: [&str; 6] = [
+    "title",
+    "author",
+    "ruleset",
+    "solution",
+    "disabledlogic",
+    "truecandidatesoptions",
+];
+
+#[derive(Deserialize)]
+struct Puzzle {
+    size: usize,
+    grid: Vec<Vec<Cell>>,
+    #[serde(rename = "diagonal+", default)]
+    positive_diagonal: bool,
+    #[serde(rename = "diagonal-", default)]
+    negative_diagonal: bool,
+    #[serde(default)]
+    killercage: Vec<CageJson>,
+    #[serde(default)]
+    cage: Vec<CageJson>,
+    #[serde(default)]
+    thermometer: Vec<LinesJson>,
+    #[serde(default)]
+    arrow: Vec<ArrowJson>,
+    #[serde(default)]
+    difference: Vec<DotJson>,
+    #[serde(default)]
+    ratio: Vec<DotJson>,
+    #[serde(flatten)]
+    other: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    #[serde(default)]
+    value: Option<usize>,
+    #[serde(default)]
+    given: bool,
+    #[serde(default)]
+    region: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct CageJson {
+    cells: Vec<String>,
+    #[serde(default)]
+    value: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct LinesJson {
+    lines: Vec<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ArrowJson {
+    cells: Vec<String>,
+    lines: Vec<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct DotJson {
+    cells: Vec<String>,
+    #[serde(default)]
+    value: Option<Value>,
+}
+
+// f-puzzles writes numbers as strings, and leaves them empty when unset
+fn number(value: &Option<Value>) -> Option<usize> {
+    match value {
+        Some(Value::Number(n)) => n.as_u64().map(|n| n as usize),
+        Some(Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// parses an "R1C1" reference into a cell index
+fn cell(reference: &str) -> Result<usize, Error> {
+    let invalid = || Error::InvalidCell {
+        got: reference.to_owned(),
+    };
+
+    let upper = reference.to_ascii_uppercase();
+    let (row, col) = upper
+        .strip_prefix('R')
+        .and_then(|rest| rest.split_once('C'))
+        .ok_or_else(invalid)?;
+    let row: usize = row.parse().map_err(|_| invalid())?;
+    let col: usize = col.parse().map_err(|_| invalid())?;
+
+    if !(1..=9).contains(&row) || !(1..=9).contains(&col) {
+        return Err(invalid());
+    }
+
+    Ok((row - 1) * 9 + col - 1)
+}
+
+fn cells(references: &[String]) -> Result<Vec<usize>, Error> {
+    references.iter().map(|r| cell(r)).collect()
+}
+
+fn is_set(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        _ => true,
+    }
+}
+
+/// Turns a payload into JSON text. The payload can be the JSON itself, its
+/// LZ-string compressed form, or an f-puzzles or SudokuPad link holding it.
+pub fn decode(payload: &str) -> Result<String, Error> {
+    let payload = payload.trim();
+    if payload.starts_with('{') {
+        return Ok(payload.to_owned());
+    }
+
+    // "https://www.f-puzzles.com/?load=..." or "https://sudokupad.app/fpuzzles..."
+    let data = match (payload.find("load="), payload.find("fpuzzles")) {
+        (Some(i), _) => &payload[i + "load=".len()..],
+        (None, Some(i)) => &payload[i + "fpuzzles".len()..],
+        (None, None) => payload,
+    };
+    let data = data.split(['&', '#']).next().unwrap_or("");
+    let data = data
+        .replace("%2B", "+")
+        .replace("%2F", "/")
+        .replace("%3D", "=")
+        .replace(' ', "+");
+
+    let json = lz_str::decompress_from_base64(&data)
+        .or_else(|| lz_str::decompress_from_encoded_uri_component(&data))
+        .and_then(|json| String::from_utf16(&json).ok())
+        .filter(|json| json.trim_start().starts_with('{'))
+        .ok_or(Error::InvalidPayload)?;
+
+    Ok(json)
+}
+
+/// Imports a puzzle, failing if it has constraints [`Variant`] can't express.
+pub fn import(payload: &str) -> Result<Variant, Error> {
+    let (variant, skipped) = import_lossy(payload)?;
+
+    if skipped.is_empty() {
+        Ok(variant)
+    } else {
+        Err(Error::Unsupported {
+            constraints: skipped,
+        })
+    }
+}
+
+/// Imports a puzzle, leaving out unsupported constraints. Their names are
+/// returned along with the puzzle.
+pub fn import_lossy(payload: &str) -> Result<(Variant, Vec<String>), Error> {
+    let json = decode(payload)?;
+    let puzzle: Puzzle = serde_json::from_str(&json).map_err(|err| Error::InvalidJson {
+        msg: err.to_string(),
+    })?;
+
+    if puzzle.size != 9 || puzzle.grid.len() != 9 || puzzle.grid.iter().any(|r| r.len() != 9) {
+        return Err(Error::InvalidSize { got: puzzle.size });
+    }
+
+    let mut variant = Variant::default();
+    let mut givens = [0; 81];
+    for (i, c) in puzzle.grid.iter().flatten().enumerate() {
+        if let (true, Some(n @ 1..=9)) = (c.given, c.value) {
+            givens[i] = n;
+        }
+        if let Some(region @ 0..=8) = c.region {
+            variant.regions[i] = region;
+        }
+    }
+    variant.givens = Sudoku::new(givens).expect("givens are between 1 and 9");
+    variant.positive_diagonal = puzzle.positive_diagonal;
+    variant.negative_diagonal = puzzle.negative_diagonal;
+
+    for cage in puzzle.killercage.iter().chain(&puzzle.cage) {
+        variant.cages.push(Cage {
+            cells: cells(&cage.cells)?,
+            sum: number(&cage.value),
+        });
+    }
+
+    for thermo in &puzzle.thermometer {
+        for line in &thermo.lines {
+            variant.thermos.push(cells(line)?);
+        }
+    }
+
+    for arrow in &puzzle.arrow {
+        let bulb = cells(&arrow.cells)?;
+        if bulb.is_empty() {
+            return Err(Error::InvalidJson {
+                msg: "arrows must have a bulb".to_owned(),
+            });
+        }
+        // lines start inside the bulb
+        let lines = arrow
+            .lines
+            .iter()
+            .map(|line| {
+                let line: Vec<usize> = cells(line)?
+                    .into_iter()
+                    .filter(|i| !bulb.contains(i))
+                    .collect();
+                if line.is_empty() {
+                    return Err(Error::InvalidJson {
+                        msg: "arrow lines must leave the bulb".to_owned(),
+                    });
+                }
+                Ok(line)
+            })
+            .collect::<Result<_, Error>>()?;
+        variant.arrows.push(Arrow { bulb, lines });
+    }
+
+    let dots = [
+        (&puzzle.difference, DotKind::Difference, 1),
+        (&puzzle.ratio, DotKind::Ratio, 2),
+    ];
+    for (list, kind, default) in dots {
+        for dot in list {
+            match cells(&dot.cells)?[..] {
+                [a, b] => variant.dots.push(Dot {
+                    cells: (a, b),
+                    kind,
+                    value: number(&dot.value).unwrap_or(default),
+                }),
+                _ => {
+                    return Err(Error::InvalidJson {
+                        msg: "dots must join two cells".to_owned(),
+                    })
+                }
+            }
+        }
+    }
+
+    let skipped = puzzle
+        .other
+        .iter()
+        .filter(|(key, value)| !IGNORED.contains(&key.as_str()) && is_set(value))
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    Ok((variant, skipped))
+}
+
+/// Imports an f-puzzles or SudokuPad puzzle. Unsupported constraints raise a
+/// `ValueError` naming them, or a warning if `skip_unsupported` is set.
+#[pyfunction(skip_unsupported = "false")]
+pub fn import_fpuzzles(py: Python<'_>, payload: &str, skip_unsupported: bool) -> PyResult<Variant> {
+    if !skip_unsupported {
+        return Ok(import(payload)?);
+    }
+
+    let (variant, skipped) = import_lossy(payload)?;
+    if !skipped.is_empty() {
+        let msg = format!("skipped unsupported constraints: {}", skipped.join(", "));
+        let category = py.import("builtins")?.getattr("UserWarning")?;
+        PyErr::warn(py, category, &msg, 1)?;
+    }
+
+    Ok(variant)
+}
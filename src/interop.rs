@@ -0,0 +1,325 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Formats shared with other solvers, for checking results against them.
+//!
+//! - DLX1, the input of Knuth's exact cover solver: a line of item names,
+//!   with primary items before a `|` and secondary items after it, then a
+//!   line per option listing its items. Lines starting with `|` are
+//!   comments. Items are columns and options are rows.
+//! - DIMACS CNF, the input of most SAT solvers, for sudoku puzzles. The
+//!   variable for placing digit `n` in cell `(r, c)` is `r * 81 + c * 9 + n`,
+//!   one more than the row of the exact cover matrix in
+//!   [`sudoku_alg`](crate::sudoku_alg).
+
+use crate::dancing_links::{DancingLinks, Error as DlxError};
+use crate::puzzle::Sudoku;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    MissingItems,
+    InvalidItem { line: usize, name: String },
+    DuplicateItem { line: usize, name: String },
+    UnknownItem { line: usize, name: String },
+    InvalidLiteral { got: String },
+    ConflictingModel { row: usize, col: usize },
+    Unsatisfiable,
+    DancingLinks { inner: DlxError },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingItems => write!(f, "no line of items found!"),
+            Error::InvalidItem { line, name } => {
+                write!(f, "line {line}: invalid item name {name:?}!")
+            }
+            Error::DuplicateItem { line, name } => {
+                write!(f, "line {line}: item {name:?} is listed twice!")
+            }
+            Error::UnknownItem { line, name } => {
+                write!(f, "line {line}: option uses unknown item {name:?}!")
+            }
+            Error::InvalidLiteral { got } => write!(f, "invalid literal: got {got:?}!"),
+            Error::ConflictingModel { row, col } => {
+                write!(f, "model places several digits in cell ({row}, {col})!")
+            }
+            Error::Unsatisfiable => write!(f, "solver found the formula unsatisfiable!"),
+            Error::DancingLinks { inner } => write!(f, "dancing links error: {inner}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::DancingLinks { inner } => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl From<DlxError> for Error {
+    fn from(error: DlxError) -> Self {
+        Error::DancingLinks { inner: error }
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyErr::new::<PyValueError, _>(err.to_string())
+    }
+}
+
+/// An exact cover problem in DLX1 form, with a name for each item. Items
+/// are numbered with the primary ones first, and options in the order they
+/// were listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dlx1 {
+    pub items: Vec<String>,
+    pub primary: usize,
+    pub options: Vec<Vec<usize>>,
+}
+
+impl Dlx1 {
+    /// Names columns `c0`, `c1` and so on. Rows covering no columns can't
+    /// be written, so they're left out.
+    pub fn from_dancing_links(dlx: &DancingLinks) -> Self {
+        Self {
+            items: (0..dlx.width()).map(|i| format!("c{i}")).collect(),
+            primary: dlx.primary(),
+            options: dlx
+                .rows()
+                .into_iter()
+                .filter(|row| !row.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Builds the matrix, with a row for each option.
+    pub fn to_dancing_links(&self) -> Result<DancingLinks, DlxError> {
+        // the fields are public, so `primary` may not be a count of items
+        let secondary =
+            self.items
+                .len()
+                .checked_sub(self.primary)
+                .ok_or(DlxError::InvalidColumn {
+                    column: self.primary,
+                    width: self.items.len(),
+                })?;
+
+        DancingLinks::from_rows(&self.options, self.primary, secondary)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('|'));
+
+        let (line, names) = lines.next().ok_or(Error::MissingItems)?;
+        let mut items = Vec::new();
+        let mut primary = None;
+        let mut numbers = HashMap::new();
+        for name in names.split_whitespace() {
+            if name == "|" && primary.is_none() {
+                primary = Some(items.len());
+                continue;
+            }
+            if name.contains(['|', ':']) {
+                return Err(Error::InvalidItem {
+                    line,
+                    name: name.to_owned(),
+                });
+            }
+            if numbers.insert(name, items.len()).is_some() {
+                return Err(Error::DuplicateItem {
+                    line,
+                    name: name.to_owned(),
+                });
+            }
+            items.push(name.to_owned());
+        }
+
+        let options = lines
+            .map(|(line, option)| {
+                option
+                    .split_whitespace()
+                    .map(|name| {
+                        numbers
+                            .get(name)
+                            .copied()
+                            .ok_or_else(|| Error::UnknownItem {
+                                line,
+                                name: name.to_owned(),
+                            })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            primary: primary.unwrap_or(items.len()),
+            items,
+            options,
+        })
+    }
+}
+
+impl std::fmt::Display for Dlx1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (primary, secondary) = self.items.split_at(self.primary);
+
+        write!(f, "{}", primary.join(" "))?;
+        if !secondary.is_empty() {
+            write!(f, " | {}", secondary.join(" "))?;
+        }
+        writeln!(f)?;
+
+        for option in &self.options {
+            let names: Vec<&str> = option.iter().map(|i| &self.items[*i][..]).collect();
+            writeln!(f, "{}", names.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Dlx1 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// The DIMACS variable for placing `digit` in `(row, col)`.
+pub fn dimacs_variable(row: usize, col: usize, digit: usize) -> usize {
+    row * 81 + col * 9 + digit
+}
+
+/// Encodes a puzzle as CNF: each cell holds exactly one digit, each row,
+/// column and box holds each digit exactly once, and the givens are unit
+/// clauses.
+pub fn to_dimacs(puzzle: &Sudoku) -> String {
+    let mut clauses: Vec<Vec<isize>> = Vec::new();
+    let var = |cell: usize, digit: usize| dimacs_variable(cell / 9, cell % 9, digit) as isize;
+
+    // exactly one of the literals is true
+    let mut exactly_one = |literals: Vec<isize>| {
+        for (i, a) in literals.iter().enumerate() {
+            for b in &literals[i + 1..] {
+                clauses.push(vec![-a, -b]);
+            }
+        }
+        clauses.push(literals);
+    };
+
+    for cell in 0..81 {
+        exactly_one((1..=9).map(|n| var(cell, n)).collect());
+    }
+    for unit in 0..27 {
+        let cells: Vec<usize> = (0..9)
+            .map(|k| match unit {
+                0..=8 => unit * 9 + k,
+                9..=17 => k * 9 + unit - 9,
+                _ => (unit - 18) / 3 * 27 + (unit - 18) % 3 * 3 + k / 3 * 9 + k % 3,
+            })
+            .collect();
+        for n in 1..=9 {
+            exactly_one(cells.iter().map(|cell| var(*cell, n)).collect());
+        }
+    }
+    for (cell, n) in puzzle.cells().iter().enumerate() {
+        if *n != 0 {
+            clauses.push(vec![var(cell, *n)]);
+        }
+    }
+
+    let mut out = String::new();
+    let givens = puzzle.cells().iter().filter(|n| **n != 0).count();
+    // writing to a String can't fail
+    writeln!(out, "c sudoku {} with {givens} givens", puzzle.to_line()).unwrap();
+    writeln!(out, "p cnf 729 {}", clauses.len()).unwrap();
+    for clause in clauses {
+        for literal in clause {
+            write!(out, "{literal} ").unwrap();
+        }
+        writeln!(out, "0").unwrap();
+    }
+
+    out
+}
+
+/// Reads the grid from a SAT solver's model of [`to_dimacs`]: either
+/// competition output with `s` and `v` lines, or a bare list of literals
+/// as written by MiniSat. Cells no true variable fills are left empty.
+pub fn parse_dimacs_model(text: &str) -> Result<Sudoku, Error> {
+    let mut cells = [0; 81];
+
+    for line in text.lines().map(str::trim) {
+        let literals = match line.split_once(' ').unwrap_or((line, "")) {
+            ("s", status) if status.trim() == "UNSATISFIABLE" => return Err(Error::Unsatisfiable),
+            ("UNSAT", _) => return Err(Error::Unsatisfiable),
+            ("c" | "s" | "SAT" | "", _) => continue,
+            ("v", literals) => literals,
+            _ => line,
+        };
+
+        for literal in literals.split_whitespace() {
+            let var = literal
+                .parse::<isize>()
+                .map_err(|_| Error::InvalidLiteral {
+                    got: literal.to_owned(),
+                })?;
+            if var <= 0 {
+                continue;
+            }
+            if var > 729 {
+                return Err(Error::InvalidLiteral {
+                    got: literal.to_owned(),
+                });
+            }
+
+            let cell = (var as usize - 1) / 9;
+            if cells[cell] != 0 {
+                return Err(Error::ConflictingModel {
+                    row: cell / 9,
+                    col: cell % 9,
+                });
+            }
+            cells[cell] = (var as usize - 1) % 9 + 1;
+        }
+    }
+
+    Ok(Sudoku::new(cells).expect("digits are 1 to 9"))
+}
+
+#[pyfunction]
+#[pyo3(name = "to_dimacs")]
+pub fn py_to_dimacs(puzzle: Sudoku) -> String {
+    to_dimacs(&puzzle)
+}
+
+#[pyfunction]
+#[pyo3(name = "parse_dimacs_model")]
+pub fn py_parse_dimacs_model(text: &str) -> PyResult<Sudoku> {
+    Ok(parse_dimacs_model(text)?)
+}
@@ -0,0 +1,101 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Serde support, enabled by the `serde` feature.
+//!
+//! The feature only adds the `Serialize` and `Deserialize` derives listed
+//! here. `serde` and `serde_json` are dependencies either way, since
+//! f-puzzles imports are parsed with them and the `sudoku` command writes
+//! JSON with `--json`.
+//!
+//! The JSON written for each type is stable: fields may be added in later
+//! versions, but existing ones keep their names and meaning.
+//!
+//! - [`Sudoku`](crate::puzzle::Sudoku), used for puzzles and solutions alike:
+//!   an array of 81 integers from 0 to 9, row by row, with 0 for empty
+//!   cells. Anything else is rejected when deserializing.
+//!
+//!   `[4, 0, 6, 7, 3, 5, 8, 1, 0, 2, 7, ...]`
+//!
+//! - [`SearchStats`](crate::dancing_links::SearchStats): the counters of a
+//!   search, with one entry in `levels` per depth of the search tree.
+//!
+//!   `{"nodes": 60, "updates": 1406, "max_depth": 50, "solutions": 1,
+//!   "levels": [{"nodes": 1, "branches": 1}, ...]}`
+//!
+//! - Errors of every module: an object whose `kind` is the variant name in
+//!   snake case, alongside the variant's fields. Errors wrapping another
+//!   error hold it in `inner`.
+//!
+//!   `{"kind": "invalid_cell", "got": 10}`,
+//!   `{"kind": "dancing_links", "inner": {"kind": "no_solutions"}}`
+//!
+//! - [`PuzzleFile`](crate::formats::PuzzleFile): `givens` and `placed` as
+//!   puzzles, `candidates` as null or 81 arrays of digits, row by row, and
+//!   `comments` as an array of strings.
+//!
+//! - [`Variant`](crate::variant::Variant): `givens` as a puzzle, `regions`
+//!   as 81 region numbers from 0 to 8, `cages` as `{"cells", "sum"}` objects
+//!   with a null sum for cages without one, `thermos` as arrays of cells from
+//!   the bulb up, `arrows` as `{"bulb", "lines"}` objects, `dots` as
+//!   `{"cells": [a, b], "kind": "difference" | "ratio", "value"}` objects,
+//!   and booleans `positive_diagonal` and `negative_diagonal`. Cells are
+//!   numbered row by row from 0 to 80.
+//!
+//! - [`Style`](crate::text::Style), [`Format`](crate::formats::Format) and
+//!   [`Grade`](crate::logic::Grade): their names in lower case, such as
+//!   `"grid"`, `"sdk"` or `"easy"`.
+//!
+//! - [`Violation`](crate::sudoku_alg::Violation): an object whose `kind` is
+//!   `"empty_cell"`, `"changed_given"` or `"repeated"`, alongside its fields.
+//!   Units are `"row"`, `"column"` or `"region"`.
+//!
+//!   `{"kind": "repeated", "unit": "row", "index": 0, "digit": 5, "cells": [0, 4]}`
+//!
+//! - [`Step`](crate::logic::Step): `{"cell", "digit", "technique"}` with the
+//!   technique in snake case, such as `"hidden_single"`.
+//!
+//! - [`Event`](crate::dancing_links::Event): an object whose `kind` is the
+//!   event in snake case, alongside its fields.
+//!
+//!   `{"kind": "try_row", "row": 12, "depth": 0}`
+//!
+//! - [`Backdoor`](crate::logic::Backdoor): `{"cells", "digits"}`, arrays of
+//!   the same length.
+
+/// (De)serializes the region of each cell of a [`Variant`](crate::variant::Variant).
+pub(crate) mod regions {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        regions: &[usize; 81],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(regions)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[usize; 81], D::Error> {
+        let regions = Vec::<usize>::deserialize(deserializer)?;
+
+        if let Some(region) = regions.iter().find(|r| **r > 8) {
+            return Err(D::Error::custom(format!("invalid region: got {region}!")));
+        }
+
+        regions
+            .try_into()
+            .map_err(|r: Vec<usize>| D::Error::invalid_length(r.len(), &"81 regions"))
+    }
+}
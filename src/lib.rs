@@ -16,24 +16,103 @@
 extern crate lazy_static;
 use pyo3::prelude::*;
 
+pub mod arrays;
+pub mod batch;
+pub mod book;
+pub mod canonical;
 pub mod dancing_links;
+pub mod exact_cover;
+pub mod formats;
+pub mod fpuzzles;
+pub mod interop;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod logic;
+pub mod puzzle;
+pub mod render;
 pub mod sudoku_alg;
+pub mod text;
+pub mod variant;
 
 #[pymodule]
-fn sudoku(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(sudoku_alg::solve, m)?)?;
+fn sudoku(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_solve, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_solve_many, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_solve_with_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_random_solution, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_count_solutions, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_verify_solution, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_redundant_clues, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_is_minimal, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_minimize, m)?)?;
+    m.add_function(wrap_pyfunction!(sudoku_alg::py_search_events, m)?)?;
     m.add_function(wrap_pyfunction!(sudoku_alg::print_puzzle, m)?)?;
+    m.add_function(wrap_pyfunction!(canonical::py_are_equivalent, m)?)?;
+    m.add_function(wrap_pyfunction!(logic::py_backdoor, m)?)?;
+    m.add_function(wrap_pyfunction!(text::py_parse, m)?)?;
+    m.add_function(wrap_pyfunction!(text::py_format, m)?)?;
+    m.add_function(wrap_pyfunction!(formats::read_file, m)?)?;
+    m.add_function(wrap_pyfunction!(formats::write_file, m)?)?;
+    m.add_function(wrap_pyfunction!(fpuzzles::import_fpuzzles, m)?)?;
+    m.add_function(wrap_pyfunction!(interop::py_to_dimacs, m)?)?;
+    m.add_function(wrap_pyfunction!(interop::py_parse_dimacs_model, m)?)?;
+    m.add_function(wrap_pyfunction!(render::render_svg, m)?)?;
+    m.add_function(wrap_pyfunction!(render::render_png, m)?)?;
+    m.add_class::<puzzle::Sudoku>()?;
+    m.add_class::<exact_cover::ExactCover>()?;
+    m.add_class::<book::Book>()?;
+    m.add_class::<exact_cover::SolutionIter>()?;
+    m.add_class::<exact_cover::EventIter>()?;
+    m.add_class::<formats::PuzzleFile>()?;
+    m.add_class::<variant::Variant>()?;
+    m.add("ShapeError", py.get_type::<arrays::ShapeError>())?;
+    m.add("DtypeError", py.get_type::<arrays::DtypeError>())?;
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::dancing_links::DancingLinks;
-    use crate::sudoku_alg::{print_puzzle, solve};
+    use crate::batch;
+    use crate::book::{Book, Error as BookError};
+    use crate::canonical::{are_equivalent, canonical_form};
+    use crate::dancing_links::{
+        Column, ColumnChooser, DancingLinks, Event, FirstColumn, MinimumRemainingValues,
+    };
+    use crate::exact_cover::ExactCover;
+    use crate::formats::{self, Format, PuzzleFile};
+    use crate::fpuzzles;
+    use crate::interop::{self, Dlx1};
+    use crate::logic::{self, Grade, Step, Technique};
+    use crate::puzzle::Sudoku;
+    use crate::render::{self, Options};
+    use crate::sudoku_alg::{
+        count_solutions, decode_row, generate, is_minimal, minimize, print_puzzle, random_solution,
+        redundant_clues, search_events, solutions, solve, solve_many, solve_with_stats,
+        verify_solution, verify_solution_in_regions, Error, Symmetry, UnitKind, Violation,
+    };
+    use crate::text::{self, Style};
+    use crate::variant::{Arrow, Cage, Dot, DotKind, Variant};
 
     type TestResult = Result<(), Box<dyn std::error::Error>>;
 
+    // the puzzle most tests start from, with a unique solution
+    const PUZZLE: &str =
+        "4.673581.278.9654....2..79..624.3.......614..1.......7...3..6...17.5...46.9...2.5";
+
+    fn puzzle() -> Sudoku {
+        PUZZLE.parse().expect("the test puzzle is valid")
+    }
+
+    // every non-empty subset of 6 items, so the covers are the 203 set partitions
+    fn subsets() -> DancingLinks {
+        let matrix: Vec<bool> = (1..64)
+            .flat_map(|row: usize| (0..6).map(move |col| row & (1 << col) != 0))
+            .collect();
+
+        DancingLinks::new(&matrix, 6, 63).expect("the subset matrix is valid")
+    }
+
     #[test]
     fn dlx_test() -> TestResult {
         // create matrix of boolean values
@@ -50,6 +129,15 @@ mod tests {
 
         println!("Encoded DLX:\n{dlx}");
 
+        // uncovering in reverse order restores the matrix
+        let mut covered = dlx.clone();
+        assert_eq!(covered.cover_column(0)?, 4);
+        covered.cover_column(3)?;
+        covered.uncover_column(3)?;
+        covered.uncover_column(0)?;
+        assert_eq!(covered, dlx);
+        assert!(covered.cover_column(7).is_err());
+
         let solutions = dlx.solve(None)?;
 
         for solution in solutions {
@@ -59,6 +147,297 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dlx_minimum_column_test() -> TestResult {
+        // column sizes are 3, 3 and 1, so column 2 must be chosen first
+        let matrix: Vec<bool> = [
+            1, 0, 0, 0, 1, 0, 1, 1, 0, 1, 0, 1, 0, 1, 0, //
+        ]
+        .iter()
+        .map(|x| *x != 0)
+        .collect();
+
+        let mut first_choice = None;
+        let mut mrv_nodes = 0;
+        let solutions =
+            DancingLinks::new(&matrix, 3, 5)?.solve_with(None, &mut |columns: &[Column]| {
+                mrv_nodes += 1;
+
+                let choice = MinimumRemainingValues.choose(columns);
+                let min = columns.iter().map(|c| c.size).min().unwrap_or(0);
+                assert_eq!(columns[choice].size, min);
+
+                if first_choice.is_none() {
+                    assert_eq!(
+                        columns.iter().map(|c| c.size).collect::<Vec<_>>(),
+                        [3, 3, 1]
+                    );
+                    first_choice = Some(columns[choice].index);
+                }
+
+                choice
+            })?;
+
+        assert_eq!(first_choice, Some(2));
+        assert_eq!(solutions, [[3, 1], [3, 4]]);
+
+        let mut first_nodes = 0;
+        DancingLinks::new(&matrix, 3, 5)?.solve_with(None, &mut |columns: &[Column]| {
+            first_nodes += 1;
+            FirstColumn.choose(columns)
+        })?;
+
+        println!(
+            "Search nodes: {mrv_nodes} (minimum remaining values), {first_nodes} (first column)"
+        );
+        assert!(mrv_nodes < first_nodes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dlx_parallel_test() -> TestResult {
+        let dlx = subsets();
+        let solutions = dlx.clone().solve(None)?;
+
+        assert_eq!(solutions.len(), 203);
+        assert_eq!(dlx.clone().count(None)?, 203);
+
+        for split_depth in [0, 1, 2, 10] {
+            for threads in [1, 4] {
+                let parallel = dlx
+                    .clone()
+                    .solve_parallel(None, split_depth, Some(threads))?;
+                assert_eq!(parallel, solutions);
+
+                let count = dlx
+                    .clone()
+                    .count_parallel(None, split_depth, Some(threads))?;
+                assert_eq!(count, 203);
+            }
+        }
+
+        // seeding the search with row {0, 1} leaves the partitions of {2, 3, 4, 5}
+        assert_eq!(dlx.count_parallel(Some(&[2]), 1, None)?, 15);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dlx_solutions_test() -> TestResult {
+        let dlx = subsets();
+
+        // the lazy iterator visits solutions in the same order as solve
+        let lazy = dlx
+            .clone()
+            .into_solutions(None)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lazy, dlx.clone().solve(None)?);
+
+        let seeded = dlx.clone().into_solutions(Some(&[2]))?;
+        assert_eq!(seeded.count(), 15);
+        assert!(dlx
+            .clone()
+            .solve(Some(&[2]))?
+            .iter()
+            .all(|s| s[0] == 2 && s[1..].iter().all(|r| *r != 2)));
+
+        // rows {0, 1} and {1, 2} overlap
+        assert!(dlx.into_solutions(Some(&[2, 5])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dlx_events_test() -> TestResult {
+        let dlx = subsets();
+
+        let mut events = dlx.clone().into_events(None)?;
+        assert!(matches!(
+            events.next(),
+            Some(Ok(Event::ChooseColumn { depth: 0, .. }))
+        ));
+
+        // pausing and cloning the search doesn't change what comes next
+        let paused = events.clone();
+        let rest = events.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(paused.collect::<Result<Vec<_>, _>>()?, rest);
+
+        let solutions: Vec<_> = rest
+            .iter()
+            .filter_map(|event| match event {
+                Event::Solution { rows } => Some(rows.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(solutions, dlx.clone().solve(None)?);
+
+        // every row tried is given up, and every cover undone
+        let count = |f: fn(&Event) -> bool| rest.iter().filter(|e| f(e)).count();
+        assert_eq!(
+            count(|e| matches!(e, Event::TryRow { .. })),
+            count(|e| matches!(e, Event::Backtrack { .. }))
+        );
+        assert_eq!(
+            count(|e| matches!(e, Event::CoverColumn { .. })),
+            count(|e| matches!(e, Event::UncoverColumn { .. }))
+        );
+
+        let puzzle = puzzle();
+        let solution = puzzle.solve()?;
+        let mut events = search_events(puzzle.cells())?;
+        let givens = puzzle.cells().iter().filter(|n| **n != 0).count();
+        assert_eq!(events.rows()?.len(), givens);
+        let mut placed = 0;
+        for event in &mut events {
+            match event? {
+                Event::TryRow { row, .. } => {
+                    let (r, c, n) = decode_row(row);
+                    assert_eq!(puzzle.get(r, c), Some(0));
+                    assert_eq!(solution.get(r, c), Some(n));
+                    placed += 1;
+                }
+                Event::Solution { rows } => assert_eq!(rows.len(), 81),
+                _ => {}
+            }
+        }
+        assert_eq!(placed, 81 - givens);
+
+        Ok(())
+    }
+
+    #[test]
+    fn exact_cover_test() -> TestResult {
+        let rows = vec![vec![0, 1], vec![2], vec![0], vec![1, 3], vec![2, 3]];
+
+        // without secondary columns, column 3 must be covered too
+        let problem = ExactCover::new(&rows, None, &[])?;
+        assert_eq!(problem.solve(None, None)?, [vec![0, 4], vec![2, 3, 1]]);
+
+        // as a secondary column it may also be left uncovered
+        let problem = ExactCover::new(&rows, None, &[3])?;
+        assert_eq!(
+            problem.solve(None, None)?,
+            [vec![0, 1], vec![0, 4], vec![2, 3, 1]]
+        );
+        assert_eq!(problem.count(None, 1, Some(2))?, 3);
+        assert_eq!(problem.solve(Some(1), None)?.len(), 1);
+        assert_eq!(problem.solve(None, Some(&[4]))?, [[4, 0]]);
+
+        assert!(ExactCover::new(&[vec![0, 0]], None, &[]).is_err());
+        assert!(ExactCover::new(&rows, Some(3), &[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn interop_test() -> TestResult {
+        // the example from Knuth's DLX1 documentation
+        let text = "| a comment
+A B C D E | F G
+C E F
+A D G
+B C F
+A D
+B G
+D E G
+";
+        let problem: Dlx1 = text.parse()?;
+        assert_eq!(problem.items.len(), 7);
+        assert_eq!(problem.primary, 5);
+        assert_eq!(problem.options[0], [2, 4, 5]);
+
+        let dlx = problem.to_dancing_links()?;
+        let mut solutions = dlx.clone().solve(None)?;
+        solutions[0].sort_unstable();
+        assert_eq!(solutions, [[0, 3, 4]]);
+
+        // writing and reading back keeps the matrix, but not the names
+        let written = Dlx1::from_dancing_links(&dlx).to_string();
+        assert!(written.starts_with("c0 c1 c2 c3 c4 | c5 c6\nc2 c4 c5\n"));
+        assert_eq!(written.parse::<Dlx1>()?.to_dancing_links()?, dlx);
+        let cover = ExactCover::from_dlx1(text)?;
+        assert_eq!(
+            ExactCover::from_dlx1(&cover.to_dlx1())?
+                .solve(None, None)?
+                .len(),
+            1
+        );
+
+        assert!(matches!(
+            "A B\nA C".parse::<Dlx1>(),
+            Err(interop::Error::UnknownItem { line: 2, .. })
+        ));
+        assert!(matches!(
+            "| only comments".parse::<Dlx1>(),
+            Err(interop::Error::MissingItems)
+        ));
+        assert!("A A".parse::<Dlx1>().is_err());
+        assert!("A | B | C".parse::<Dlx1>().is_err());
+        assert!("A B\nA A".parse::<Dlx1>()?.to_dancing_links().is_err());
+        let too_many_primary = Dlx1 {
+            primary: 8,
+            ..problem
+        };
+        assert!(matches!(
+            too_many_primary.to_dancing_links(),
+            Err(crate::dancing_links::Error::InvalidColumn {
+                column: 8,
+                width: 7
+            })
+        ));
+
+        let puzzle = puzzle();
+        let solution = puzzle.solve()?;
+        let cnf = interop::to_dimacs(&puzzle);
+        let givens = puzzle.cells().iter().filter(|n| **n != 0).count();
+        let header = format!("p cnf 729 {}", 81 * 37 + 243 * 37 + givens);
+        assert_eq!(cnf.lines().nth(1), Some(&header[..]));
+
+        // the solution satisfies every clause, and a wrong grid doesn't
+        let is_true = |grid: &Sudoku, literal: isize| {
+            let var = literal.unsigned_abs() - 1;
+            let set = grid.cells()[var / 9] == var % 9 + 1;
+            set == (literal > 0)
+        };
+        let satisfies = |grid: &Sudoku| {
+            cnf.lines().skip(2).all(|clause| {
+                clause
+                    .split_whitespace()
+                    .map(|l| l.parse::<isize>().unwrap())
+                    .any(|l| l != 0 && is_true(grid, l))
+            })
+        };
+        assert!(satisfies(&solution));
+        assert!(!satisfies(
+            &solution.permute_digits(&[2, 1, 3, 4, 5, 6, 7, 8, 9])?
+        ));
+
+        let model: Vec<String> = (0..81)
+            .flat_map(|cell| {
+                let n = solution.cells()[cell];
+                (1..=9).map(move |d| {
+                    let var = interop::dimacs_variable(cell / 9, cell % 9, d) as isize;
+                    (if d == n { var } else { -var }).to_string()
+                })
+            })
+            .collect();
+        let output = format!("c comment\ns SATISFIABLE\nv {} 0\n", model.join(" "));
+        assert_eq!(interop::parse_dimacs_model(&output)?, solution);
+        assert_eq!(
+            interop::parse_dimacs_model(&format!("SAT\n{} 0\n", model.join(" ")))?,
+            solution
+        );
+        assert!(matches!(
+            interop::parse_dimacs_model("s UNSATISFIABLE"),
+            Err(interop::Error::Unsatisfiable)
+        ));
+        assert!(interop::parse_dimacs_model("v 1 2 0").is_err());
+        assert!(interop::parse_dimacs_model("v 730 0").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn sudoku_test() -> TestResult {
         // create sudoku puzzle
@@ -79,4 +458,859 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn solve_many_test() -> TestResult {
+        let puzzle = vec![
+            4, 0, 6, 7, 3, 5, 8, 1, 0, 2, 7, 8, 0, 9, 6, 5, 4, 0, 0, 0, 0, 2, 0, 0, 7, 9, 0, 0, 6,
+            2, 4, 0, 3, 0, 0, 0, 0, 0, 0, 0, 6, 1, 4, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 3,
+            0, 0, 6, 0, 0, 0, 1, 7, 0, 5, 0, 0, 0, 4, 6, 0, 9, 0, 0, 0, 2, 0, 5,
+        ];
+        let solution = solve(puzzle.clone())?;
+
+        let mut bad_cell = puzzle.clone();
+        bad_cell[1] = 10;
+
+        // an almost empty grid has far too many solutions to list
+        let mut open = vec![0; 81];
+        open[..9].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let puzzles = vec![puzzle.clone(), vec![0; 80], bad_cell, puzzle, open];
+        let results = solve_many(puzzles, Some(2));
+
+        // bad puzzles fail on their own without affecting the rest of the batch
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().ok(), Some(&solution));
+        assert!(matches!(results[1], Err(Error::InvalidGrid { got: 80 })));
+        assert!(matches!(results[2], Err(Error::InvalidCell { got: 10 })));
+        assert_eq!(results[3].as_ref().ok(), Some(&solution));
+        assert!(matches!(
+            results[4],
+            Err(Error::MultipleSolutions { found: 2 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn sudoku_stats_test() -> TestResult {
+        let puzzle = vec![
+            0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 0, 0, 3, 5, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 7, 0, 7, 0,
+            0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 4, 0, 0, 8, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            2, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 4, 0, 0, 5, 0, 0, 0, 0, 6, 0, 0,
+        ];
+
+        let (solution, stats) = solve_with_stats(puzzle.clone())?;
+
+        println!("Search stats: {stats:?}");
+
+        assert_eq!(solution, solve(puzzle)?);
+        assert_eq!(stats.solutions, 1);
+        assert_eq!(stats.levels.len(), stats.max_depth + 1);
+        assert_eq!(
+            stats.nodes,
+            stats.levels.iter().map(|l| l.nodes).sum::<usize>()
+        );
+        assert!(stats.updates > stats.nodes);
+        assert_eq!(stats.levels[0].nodes, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn count_solutions_test() -> TestResult {
+        let mut puzzle = vec![
+            4, 0, 6, 7, 3, 5, 8, 1, 0, 2, 7, 8, 0, 9, 6, 5, 4, 0, 0, 0, 0, 2, 0, 0, 7, 9, 0, 0, 6,
+            2, 4, 0, 3, 0, 0, 0, 0, 0, 0, 0, 6, 1, 4, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 3,
+            0, 0, 6, 0, 0, 0, 1, 7, 0, 5, 0, 0, 0, 4, 6, 0, 9, 0, 0, 0, 2, 0, 5,
+        ];
+
+        assert_eq!(count_solutions(&puzzle, 2, None)?, 1);
+
+        // removing clues can only add solutions
+        for cell in puzzle.iter_mut().take(9) {
+            *cell = 0;
+        }
+        let count = count_solutions(&puzzle, 2, Some(4))?;
+        assert!(count > 1);
+        assert_eq!(count_solutions(&puzzle, 0, Some(1))?, count);
+
+        Ok(())
+    }
+
+    #[test]
+    fn random_solution_test() -> TestResult {
+        let empty = vec![0; 81];
+
+        let a = random_solution(empty.clone(), 1)?;
+        let b = random_solution(empty.clone(), 1)?;
+        let c = random_solution(empty, 2)?;
+
+        println!("Random solution");
+        print_puzzle(Vec::from(a))?;
+
+        // same seed gives the same grid, and each grid is a valid sudoku
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(solve(Vec::from(a))?, a);
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_test() -> TestResult {
+        let puzzle = generate(7)?;
+        assert_eq!(generate(7)?, puzzle);
+        assert_eq!(solutions(&puzzle, 2)?.len(), 1);
+
+        // every clue is needed
+        for i in (0..81).filter(|i| puzzle[*i] != 0) {
+            let mut fewer = puzzle;
+            fewer[i] = 0;
+            assert_eq!(solutions(&fewer, 2)?.len(), 2);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_solution_test() -> TestResult {
+        let puzzle = puzzle();
+        let solution = solve(puzzle.cells().to_vec())?;
+
+        assert_eq!(verify_solution(puzzle.cells(), &solution)?, []);
+
+        // swapping two digits of a row keeps the row but breaks its columns
+        let mut wrong = solution;
+        wrong.swap(0, 1);
+        wrong[80] = 0;
+        let violations = verify_solution(puzzle.cells(), &wrong)?;
+        assert_eq!(
+            violations[0],
+            Violation::ChangedGiven {
+                cell: 0,
+                given: 4,
+                got: solution[1]
+            }
+        );
+        assert_eq!(violations[1], Violation::EmptyCell { cell: 80 });
+        let other = (9..81).step_by(9).find(|i| wrong[*i] == wrong[0]).unwrap();
+        assert!(violations.contains(&Violation::Repeated {
+            unit: UnitKind::Column,
+            index: 0,
+            digit: wrong[0],
+            cells: vec![0, other],
+        }));
+        assert!(!violations.iter().any(|v| matches!(
+            v,
+            Violation::Repeated {
+                unit: UnitKind::Row,
+                ..
+            }
+        )));
+
+        // moving a cell between two boxes makes each repeat a digit
+        let mut regions: Vec<usize> = (0..81).map(|i| (i / 27) * 3 + (i % 9) / 3).collect();
+        regions.swap(0, 80);
+        let violations = verify_solution_in_regions(puzzle.cells(), &solution, &regions)?;
+        let repeated: Vec<_> = violations
+            .iter()
+            .map(|v| match v {
+                Violation::Repeated { unit, index, .. } => (*unit, *index),
+                _ => unreachable!("solution is complete"),
+            })
+            .collect();
+        assert_eq!(repeated, [(UnitKind::Region, 0), (UnitKind::Region, 8)]);
+
+        assert!(matches!(
+            verify_solution(puzzle.cells(), &solution[..80]),
+            Err(Error::InvalidGrid { got: 80 })
+        ));
+        assert!(matches!(
+            verify_solution_in_regions(puzzle.cells(), &solution, &[9; 81]),
+            Err(Error::InvalidRegion { got: 9 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_test() -> TestResult {
+        let puzzle = puzzle();
+        let canonical = canonical_form(&puzzle);
+
+        // digits are relabeled in order, with the fullest rows first
+        assert_eq!(&canonical.cells()[..6], [1, 2, 3, 4, 5, 0]);
+        assert_eq!(canonical_form(&canonical), canonical);
+
+        // transpose, swap the first two bands, relabel 1 and 2, then swap
+        // the first two columns
+        let mut cells = [0; 81];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            let (r, c) = (i / 9, i % 9);
+            let r = if r < 6 { (r + 3) % 6 } else { r };
+            let c = match c {
+                0 => 1,
+                1 => 0,
+                c => c,
+            };
+            *cell = match puzzle.cells()[c * 9 + r] {
+                1 => 2,
+                2 => 1,
+                n => n,
+            };
+        }
+        let other = Sudoku::new(cells)?;
+        assert_ne!(other, puzzle);
+        assert_eq!(canonical_form(&other), canonical);
+        assert!(are_equivalent(&puzzle, &other));
+
+        // moving a given to another row isn't a symmetry
+        let mut moved = puzzle;
+        moved.set(0, 0, 0)?;
+        moved.set(4, 0, 4)?;
+        assert!(!are_equivalent(&puzzle, &moved));
+        assert!(!are_equivalent(&puzzle, &Sudoku::default()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn minimize_test() -> TestResult {
+        let puzzle = puzzle();
+        let cells = puzzle.cells();
+        let solution = solve(cells.to_vec())?;
+
+        let redundant = redundant_clues(cells)?;
+        assert!(!redundant.is_empty());
+        assert!(redundant.iter().all(|i| cells[*i] != 0));
+        assert!(!is_minimal(cells)?);
+
+        let minimal = minimize(cells, Symmetry::None)?;
+        assert!(is_minimal(&minimal)?);
+        assert!(redundant_clues(&minimal)?.is_empty());
+        assert_eq!(solve(minimal.to_vec())?, solution);
+        assert!((0..81).all(|i| minimal[i] == 0 || minimal[i] == cells[i]));
+        assert!(is_minimal(&generate(3)?)?);
+
+        // starting from the solution, the clues keep their symmetry
+        let clues = |p: &Sudoku| p.cells().map(|n| n != 0);
+        let half_turn = Sudoku::new(minimize(&solution, Symmetry::HalfTurn)?)?;
+        assert_eq!(clues(&half_turn.rotate(2)), clues(&half_turn));
+        assert_eq!(half_turn.solve()?.cells(), &solution);
+        let quarter_turn = Sudoku::new(minimize(&solution, Symmetry::QuarterTurn)?)?;
+        assert_eq!(clues(&quarter_turn.rotate(1)), clues(&quarter_turn));
+        let diagonal = Sudoku::new(minimize(&solution, Symmetry::Diagonal)?)?;
+        assert_eq!(clues(&diagonal.transpose()), clues(&diagonal));
+
+        assert!("spiral".parse::<Symmetry>().is_err());
+        assert_eq!("anti_diagonal".parse::<Symmetry>()?, Symmetry::AntiDiagonal);
+        assert!(matches!(
+            minimize(&[0; 81], Symmetry::None),
+            Err(Error::MultipleSolutions { found: 2 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn batch_test() -> TestResult {
+        // the fourth puzzle is cut short, the fifth has conflicting givens
+        // and the last has too many solutions to list
+        let corpus = format!(
+            "# comment
+{PUZZLE} easy
+
+8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..
+{}
+11...............................................................................
+123456789{}
+",
+            &PUZZLE[..79],
+            ".".repeat(72)
+        );
+        let report = batch::solve_corpus(corpus.as_bytes())?;
+
+        // failed puzzles don't count towards the timings
+        assert_eq!(report.puzzles, 5);
+        assert_eq!(report.times.len(), 2);
+        let lines: Vec<usize> = report.failures.iter().map(|f| f.line).collect();
+        assert_eq!(lines, [5, 6, 7]);
+        assert!(report.nodes >= 2 * 81 - 36 - 21);
+        assert!(report.percentile(50.0) <= report.percentile(100.0));
+        assert_eq!(
+            report.percentile(100.0),
+            *report.times.iter().max().unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn logic_test() -> TestResult {
+        let puzzle = puzzle();
+
+        // 2 can only go in the last cell of the first row
+        assert_eq!(
+            logic::next_single(&puzzle),
+            Some(Step {
+                cell: 8,
+                digit: 2,
+                technique: Technique::HiddenSingle
+            })
+        );
+        assert_eq!(logic::grade(&puzzle)?, Grade::Easy);
+
+        // a puzzle with no singles at all
+        let hard: Sudoku =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+                .parse()?;
+        assert_eq!(logic::next_single(&hard), None);
+        assert_eq!(logic::grade(&hard)?, Grade::Expert);
+
+        assert!(matches!(
+            logic::grade(&Sudoku::default()),
+            Err(Error::MultipleSolutions { found: 2 })
+        ));
+
+        // singles finish the easy puzzle without help
+        let backdoor = logic::backdoor(&puzzle, 3)?.unwrap();
+        assert_eq!(backdoor.size(), 0);
+
+        // the hard one needs two digits, one isn't enough
+        assert_eq!(logic::backdoor(&hard, 1)?, None);
+        let backdoor = logic::backdoor(&hard, 3)?.unwrap();
+        assert_eq!(backdoor.size(), 2);
+        let mut helped = hard;
+        let solution = hard.solve()?;
+        for (cell, digit) in backdoor.cells.iter().zip(&backdoor.digits) {
+            assert_eq!(solution.cells()[*cell], *digit);
+            helped.set(cell / 9, cell % 9, *digit)?;
+        }
+        assert_eq!(logic::backdoor(&helped, 0)?.map(|b| b.size()), Some(0));
+        assert!(logic::backdoor(&Sudoku::default(), 3).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sudoku_class_test() -> TestResult {
+        let line = PUZZLE;
+        let puzzle: Sudoku = line.parse()?;
+
+        assert_eq!(puzzle.to_line(), line);
+        assert_eq!(puzzle.get(0, 0), Some(4));
+        assert_eq!(puzzle.get(9, 0), None);
+        assert!(puzzle.is_valid());
+
+        // (0, 1) shares a row, column or box with every digit except 9
+        assert_eq!(puzzle.candidates()[1], [9]);
+        assert!(puzzle.candidates()[0].is_empty());
+
+        let solution = puzzle.solve()?;
+        assert_eq!(solution.cells(), &solve(puzzle.cells().to_vec())?);
+        assert_eq!(
+            solution.to_string(),
+            crate::sudoku_alg::format_puzzle(solution.cells())?
+        );
+
+        let mut invalid = puzzle;
+        invalid.set(0, 1, 4)?;
+        assert!(!invalid.is_valid());
+        assert!(invalid.set(0, 1, 10).is_err());
+        assert!("123".parse::<Sudoku>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn transform_test() -> TestResult {
+        let puzzle = puzzle();
+        let solution = puzzle.solve()?;
+
+        assert_eq!(puzzle.rotate(1).get(0, 8), puzzle.get(0, 0));
+        assert_eq!(
+            puzzle.rotate(2),
+            puzzle.mirror_left_right().mirror_top_bottom()
+        );
+        assert_eq!(puzzle.rotate(1).rotate(3), puzzle);
+        assert_eq!(puzzle.rotate(3), puzzle.transpose().mirror_top_bottom());
+        assert_eq!(puzzle.transpose().get(1, 0), puzzle.get(0, 1));
+
+        let swapped = puzzle.permute_rows(0, &[1, 0, 2])?;
+        assert_eq!(swapped.cells()[..9], puzzle.cells()[9..18]);
+        let swapped = puzzle.permute_columns(2, &[2, 1, 0])?;
+        assert_eq!(swapped.get(0, 6), puzzle.get(0, 8));
+        let swapped = puzzle.permute_bands(&[2, 0, 1])?;
+        assert_eq!(swapped.cells()[..27], puzzle.cells()[54..]);
+        let swapped = puzzle.permute_stacks(&[1, 2, 0])?;
+        assert_eq!(swapped.get(4, 0), puzzle.get(4, 3));
+        let relabeled = puzzle.permute_digits(&[9, 8, 7, 6, 5, 4, 3, 2, 1])?;
+        assert_eq!(relabeled.get(0, 0), Some(6));
+
+        // solving commutes with every transformation
+        let transforms: [&dyn Fn(&Sudoku) -> Sudoku; 5] = [
+            &|p| p.rotate(1),
+            &|p| p.mirror_left_right(),
+            &|p| p.permute_rows(1, &[2, 0, 1]).unwrap(),
+            &|p| p.permute_digits(&[2, 1, 3, 4, 5, 6, 7, 8, 9]).unwrap(),
+            &|p| p.random_isomorph(7),
+        ];
+        for transform in transforms {
+            assert_eq!(transform(&puzzle).solve()?, transform(&solution));
+        }
+
+        let isomorph = puzzle.random_isomorph(1);
+        assert_eq!(isomorph, puzzle.random_isomorph(1));
+        assert_ne!(isomorph, puzzle.random_isomorph(2));
+        assert!(are_equivalent(&isomorph, &puzzle));
+
+        assert!(matches!(
+            puzzle.permute_rows(3, &[0, 1, 2]),
+            Err(Error::InvalidBand { got: 3 })
+        ));
+        assert!(puzzle.permute_bands(&[0, 0, 1]).is_err());
+        assert!(puzzle.permute_stacks(&[0, 1]).is_err());
+        assert!(puzzle.permute_digits(&[1, 2, 3]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_format_test() -> TestResult {
+        let puzzle = puzzle();
+
+        // every plain text style parses back to the same puzzle
+        for style in [Style::Line, Style::Dotted, Style::Grid, Style::Compact] {
+            assert_eq!(text::parse(&text::format(&puzzle, style))?, puzzle);
+        }
+        assert_eq!(text::parse(&puzzle.solve()?.to_string())?, puzzle.solve()?);
+
+        // trailing spaces may be lost when grids are saved to files
+        let grid = puzzle.to_string();
+        let trimmed: Vec<&str> = grid.lines().map(str::trim_end).collect();
+        assert_eq!(text::parse(&trimmed.join("\n"))?, puzzle);
+
+        let boxed = "
+            +-------+-------+-------+
+            | 4 . 6 | 7 3 5 | 8 1 . |
+            | 2 7 8 | . 9 6 | 5 4 . |
+            | . . . | 2 . . | 7 9 . |
+            +-------+-------+-------+
+            | . 6 2 | 4 . 3 | . . . |
+            | . . . | . 6 1 | 4 . . |
+            | 1 . . | . . . | . . 7 |
+            +-------+-------+-------+
+            | . . . | 3 . . | 6 . . |
+            | . 1 7 | . 5 . | . . 4 |
+            | 6 . 9 | . . . | 2 . 5 |
+            +-------+-------+-------+";
+        assert_eq!(text::parse(boxed)?, puzzle);
+
+        assert_eq!(
+            text::parse("4.6x"),
+            Err(text::Error::InvalidCharacter {
+                line: 1,
+                column: 4,
+                got: 'x'
+            })
+        );
+        assert_eq!(
+            text::parse("4.6"),
+            Err(text::Error::InvalidCellCount { got: 3 })
+        );
+
+        let lines = text::format(&puzzle, Style::Boxed);
+        assert_eq!(lines.lines().count(), 19);
+        assert!(lines.starts_with("┏━━━┯━━━┯━━━┳"));
+        assert!(lines.contains("┃ 4 │   │ 6 ┃ 7 │"));
+
+        // candidates of r1c2 are only 9, and r1c1 is placed
+        let pencil = text::format(&puzzle, Style::Pencil);
+        let lines: Vec<&str> = pencil.lines().collect();
+        assert_eq!(lines.len(), 29);
+        assert_eq!(&lines[0][..8], "    ... ");
+        assert_eq!(&lines[1][..8], " 4  ... ");
+        assert_eq!(&lines[2][..8], "    ..9 ");
+        assert_eq!(lines[9], "------------+-------------+------------");
+
+        // solved digits are coloured, givens are bold
+        let solution = puzzle.solve()?;
+        let ansi = text::format_cells(solution.cells(), Some(puzzle.cells()), Style::Ansi)?;
+        assert!(ansi.starts_with("\x1b[1m4\x1b[0m \x1b[34m9\x1b[0m"));
+        assert_eq!(ansi.lines().nth(3), Some("------+-------+------"));
+
+        // 4x4 grids with 2x2 boxes
+        let small = [1, 0, 0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 2];
+        assert_eq!(
+            text::format_cells(&small, None, Style::Grid)?,
+            "1   |    \n    | 1  \n----+----\n  1 |    \n    |   2\n"
+        );
+        assert_eq!(
+            text::format_cells(&[0; 80], None, Style::Grid),
+            Err(text::Error::InvalidGridSize { got: 80 })
+        );
+        assert_eq!(
+            text::format_cells(&[5; 16], None, Style::Line),
+            Err(text::Error::InvalidCell { got: 5, size: 4 })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn puzzle_file_test() -> TestResult {
+        let puzzle = puzzle();
+        let mut file = PuzzleFile::new(puzzle);
+        file.comments = vec!["A Nathan".to_owned()];
+
+        // plain grids round trip in every format
+        for format in [Format::Sdk, Format::Ss] {
+            assert_eq!(
+                formats::read(&formats::write(&file, format), format)?.givens,
+                puzzle
+            );
+        }
+        let sdk = formats::write(&file, Format::Sdk);
+        assert_eq!(formats::read(&sdk, Format::Sdk)?, file);
+
+        // placed digits and pencil marks survive too
+        file.placed.set(0, 1, 9)?;
+        let mut candidates = file.current().candidates();
+        candidates[8].retain(|n| *n != 9);
+        file.candidates = Some(candidates);
+        let saved = file.candidates.clone().unwrap_or_default();
+        for format in [Format::Sdk, Format::Ss, Format::Sdx] {
+            let read = formats::read(&formats::write(&file, format), format)?;
+            let candidates = read.candidates.unwrap_or_default();
+
+            // cells with a single pencil mark read back as placed digits
+            assert_eq!(read.givens, puzzle);
+            assert_eq!(read.placed.cells()[1], 9);
+            for i in 0..81 {
+                match saved[i][..] {
+                    [n] => assert_eq!(read.placed.cells()[i], n),
+                    _ => assert_eq!(candidates[i], saved[i]),
+                }
+            }
+        }
+
+        let sdx = formats::write(&file, Format::Sdx);
+        assert!(sdx.starts_with("4 u9 6 7 3 5 8 1 u2"));
+
+        // sdx cells without candidates still convert to readable grids
+        let rows: Vec<String> = PUZZLE
+            .as_bytes()
+            .chunks(9)
+            .map(|row| {
+                let cells: Vec<String> = row.iter().map(|c| (*c as char).to_string()).collect();
+                cells.join(" ")
+            })
+            .collect();
+        let bare = formats::read(&rows.join("\n"), Format::Sdx)?;
+        for format in [Format::Sdk, Format::Ss] {
+            let read = formats::read(&formats::write(&bare, format), format)?;
+            assert_eq!(read.givens, puzzle);
+            let candidates = read.candidates.unwrap_or_default();
+            for (i, expected) in puzzle.candidates().iter().enumerate() {
+                match expected[..] {
+                    [n] => assert_eq!(read.placed.cells()[i], n),
+                    _ => assert_eq!(&candidates[i], expected),
+                }
+            }
+        }
+
+        assert_eq!(
+            formats::read("4.6735812\n278x96540", Format::Sdk),
+            Err(formats::Error::InvalidCharacter {
+                line: 2,
+                column: 4,
+                got: 'x'
+            })
+        );
+        assert_eq!(
+            formats::read("#C comment\n4.6735812\n278.9654.\n", Format::Ss),
+            Err(formats::Error::MissingRows {
+                line: 4,
+                got: 2,
+                expected: 9
+            })
+        );
+        // pencil marks cut short after the givens
+        let rows: Vec<&str> = (0..9).map(|r| &PUZZLE[r * 9..r * 9 + 9]).collect();
+        let marks = "1 2 3 4 5 6 7 8 9\n".repeat(2);
+        assert_eq!(
+            formats::read(&format!("{}\n{marks}", rows.join("\n")), Format::Sdk),
+            Err(formats::Error::MissingRows {
+                line: 12,
+                got: 11,
+                expected: 18
+            })
+        );
+        assert_eq!(
+            formats::read(&sdx.replacen("u9", "u99", 1), Format::Sdx),
+            Err(formats::Error::InvalidCharacter {
+                line: 1,
+                column: 3,
+                got: 'u'
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn variant_test() -> TestResult {
+        let puzzle = puzzle();
+        let solution = puzzle.solve()?;
+
+        // clear the first row and pin it down with variant constraints
+        let mut cells = *puzzle.cells();
+        cells[..9].fill(0);
+        let mut variant = Variant::new(Sudoku::new(cells)?);
+        variant.cages.push(Cage {
+            cells: vec![0, 1, 2],
+            sum: Some(19),
+        });
+        variant.cages.push(Cage {
+            cells: vec![3, 4],
+            sum: Some(10),
+        });
+        variant.thermos.push(vec![20, 21, 22]);
+        variant.arrows.push(Arrow {
+            bulb: vec![1],
+            lines: vec![vec![11, 20]],
+        });
+        variant.dots.push(Dot {
+            cells: (7, 8),
+            kind: DotKind::Difference,
+            value: 1,
+        });
+        variant.dots.push(Dot {
+            cells: (12, 21),
+            kind: DotKind::Ratio,
+            value: 2,
+        });
+        assert_eq!(variant.solve()?, solution);
+
+        // a cage sum the givens can't reach has no solutions
+        variant.cages[0].sum = Some(5);
+        assert!(variant.solutions(None)?.is_empty());
+
+        // the same puzzle as f-puzzles JSON, compressed into a link
+        let grid: Vec<String> = cells
+            .chunks(9)
+            .map(|row| {
+                let row: Vec<String> = row
+                    .iter()
+                    .map(|n| match n {
+                        0 => "{}".to_owned(),
+                        n => format!("{{\"value\":{n},\"given\":true}}"),
+                    })
+                    .collect();
+                format!("[{}]", row.join(","))
+            })
+            .collect();
+        let json = format!(
+            r#"{{"size":9,"title":"Test","grid":[{}],
+                "killercage":[{{"cells":["R1C1","R1C2","R1C3"],"value":"19"}},
+                    {{"cells":["R1C4","R1C5"],"value":10}}],
+                "thermometer":[{{"lines":[["R3C3","R3C4","R3C5"]]}}],
+                "arrow":[{{"cells":["R1C2"],"lines":[["R1C2","R2C3","R3C3"]]}}],
+                "difference":[{{"cells":["R1C8","R1C9"]}}],
+                "ratio":[{{"cells":["R2C4","R3C4"]}}],
+                "diagonal-":false}}"#,
+            grid.join(",")
+        );
+        let link = format!(
+            "https://www.f-puzzles.com/?load={}",
+            lz_str::compress_to_base64(json.as_str())
+        );
+
+        variant.cages[0].sum = Some(19);
+        assert_eq!(fpuzzles::import(&link)?, variant);
+        assert_eq!(fpuzzles::import(&json)?, variant);
+
+        let json = json.replace(r#""diagonal-":false"#, r#""antiknight":true,"odd":[]"#);
+        assert_eq!(
+            fpuzzles::import(&json),
+            Err(fpuzzles::Error::Unsupported {
+                constraints: vec!["antiknight".to_owned()]
+            })
+        );
+        assert_eq!(fpuzzles::import_lossy(&json)?.0, variant);
+        assert_eq!(
+            fpuzzles::import("not a puzzle"),
+            Err(fpuzzles::Error::InvalidPayload)
+        );
+
+        // arrows need a bulb and a line leading out of it
+        let no_bulb = json.replace(r#""cells":["R1C2"],"lines""#, r#""cells":[],"lines""#);
+        assert!(matches!(
+            fpuzzles::import_lossy(&no_bulb),
+            Err(fpuzzles::Error::InvalidJson { .. })
+        ));
+        let no_line = json.replace(r#"[["R1C2","R2C3","R3C3"]]"#, r#"[["R1C2"]]"#);
+        assert!(matches!(
+            fpuzzles::import_lossy(&no_line),
+            Err(fpuzzles::Error::InvalidJson { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_test() -> TestResult {
+        let puzzle = puzzle();
+        let solution = puzzle.solve()?;
+        let mut variant = Variant::new(puzzle);
+        variant.cages.push(Cage {
+            cells: vec![1, 10],
+            sum: Some(16),
+        });
+        variant.thermos.push(vec![20, 21, 22]);
+
+        // givens and filled digits are drawn in their own colours
+        let options = Options::default();
+        let svg = render::svg(&variant, &solution, &options);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches(&options.given_color).count(), 36);
+        assert_eq!(svg.matches(&options.filled_color).count(), 45);
+        assert!(svg.contains(">16</text>"));
+
+        // pencil marks only appear in empty cells
+        let options = Options {
+            pencil_marks: true,
+            highlights: vec![0, 80],
+            ..Options::default()
+        };
+        let svg = render::svg(&variant, &Sudoku::default(), &options);
+        let marks: usize = puzzle.candidates().iter().map(Vec::len).sum();
+        assert_eq!(svg.matches("#666666").count(), marks);
+        assert_eq!(svg.matches(&options.highlight_color).count(), 2);
+
+        let png = render::png(&variant, &solution, &options)?;
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        // colours can't break out of their attributes
+        let sneaky = Options {
+            given_color: r#"red"/><script>alert(1)</script><g x=""#.to_owned(),
+            ..Options::default()
+        };
+        let svg = render::svg(&variant, &solution, &sneaky);
+        assert!(!svg.contains("<script>"));
+        assert!(svg.contains("fill=\"red&quot;/&gt;&lt;script&gt;"));
+
+        // arrows without a bulb are skipped rather than drawn
+        let svg = render::svg(&variant, &solution, &options);
+        variant.arrows.push(Arrow {
+            bulb: vec![],
+            lines: vec![vec![1, 2]],
+        });
+        assert_eq!(render::svg(&variant, &solution, &options), svg);
+
+        Ok(())
+    }
+
+    #[test]
+    fn book_test() -> TestResult {
+        let puzzle = puzzle();
+        let mut book = Book::new("Weekly <Sudoku>");
+        for n in 0..5 {
+            book.add(Variant::new(puzzle), None, Some("Easy"));
+            assert_eq!(book.entries[n].title, format!("Puzzle {}", n + 1));
+        }
+
+        // two pages of puzzles and one of solutions
+        let pages = book.pages()?;
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].contains("Weekly &lt;Sudoku&gt;"));
+        assert!(pages[0].contains("Puzzle 4 · Easy"));
+        assert!(!pages[0].contains("Puzzle 5"));
+        assert!(pages[2].contains("Puzzle 5 · Easy"));
+
+        let pdf = book.pdf()?;
+        assert!(pdf.starts_with(b"%PDF"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.matches("/Type /Page").count(), 4); // and /Pages
+
+        // every puzzle needs a solution for the back pages
+        book.add(Variant::default(), Some("Empty"), None);
+        assert!(matches!(book.pdf(), Err(BookError::Unsolvable { .. })));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() -> TestResult {
+        use crate::dancing_links::{Error as DlxError, SearchStats};
+
+        let puzzle = puzzle();
+
+        // puzzles are plain arrays of 81 cells
+        let json = serde_json::to_string(&puzzle)?;
+        assert!(json.starts_with("[4,0,6,7,3,5,8,1,0,2,"));
+        assert_eq!(serde_json::from_str::<Sudoku>(&json)?, puzzle);
+        assert!(serde_json::from_str::<Sudoku>("[1, 2, 3]").is_err());
+        assert!(serde_json::from_str::<Sudoku>(&json.replacen('4', "10", 1)).is_err());
+
+        let (_, stats) = solve_with_stats(puzzle.cells().to_vec())?;
+        let json = serde_json::to_value(&stats)?;
+        assert_eq!(json["solutions"], 1);
+        assert_eq!(json["levels"][0]["nodes"], 1);
+        assert_eq!(serde_json::from_value::<SearchStats>(json)?, stats);
+
+        // errors are tagged with their kind
+        let err = Error::DancingLinks {
+            inner: DlxError::ConflictingPartialSolution { row: 3 },
+        };
+        let json = serde_json::to_string(&err)?;
+        assert_eq!(
+            json,
+            r#"{"kind":"dancing_links","inner":{"kind":"conflicting_partial_solution","row":3}}"#
+        );
+        let err: Error = serde_json::from_str(&json)?;
+        assert_eq!(serde_json::to_string(&err)?, json);
+        let err = text::Error::InvalidCellCount { got: 3 };
+        assert_eq!(
+            serde_json::from_str::<text::Error>(&serde_json::to_string(&err)?)?,
+            err
+        );
+
+        let violation = Violation::Repeated {
+            unit: UnitKind::Row,
+            index: 0,
+            digit: 5,
+            cells: vec![0, 4],
+        };
+        let json = serde_json::to_string(&violation)?;
+        assert_eq!(
+            json,
+            r#"{"kind":"repeated","unit":"row","index":0,"digit":5,"cells":[0,4]}"#
+        );
+        assert_eq!(serde_json::from_str::<Violation>(&json)?, violation);
+
+        let mut variant = Variant::new(puzzle);
+        variant.regions.swap(0, 80);
+        variant.cages.push(Cage {
+            cells: vec![0, 1],
+            sum: None,
+        });
+        variant.dots.push(Dot {
+            cells: (7, 8),
+            kind: DotKind::Difference,
+            value: 1,
+        });
+        let json = serde_json::to_value(&variant)?;
+        assert_eq!(json["dots"][0]["kind"], "difference");
+        assert_eq!(serde_json::from_value::<Variant>(json)?, variant);
+
+        let mut file = PuzzleFile::new(puzzle);
+        file.candidates = Some(puzzle.candidates());
+        let json = serde_json::to_string(&file)?;
+        assert_eq!(serde_json::from_str::<PuzzleFile>(&json)?, file);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,354 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Solving steps a person would take, used for hints and difficulty grades.
+
+use crate::dancing_links::{Error as DlxError, Event};
+use crate::puzzle::Sudoku;
+use crate::sudoku_alg::{self, Error};
+use pyo3::{prelude::*, types::PyDict};
+
+/// How a digit was found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Technique {
+    /// the only place left for a digit in a row, column or box
+    HiddenSingle,
+    /// the only digit left for a cell
+    NakedSingle,
+}
+
+impl std::fmt::Display for Technique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Technique::HiddenSingle => write!(f, "hidden single"),
+            Technique::NakedSingle => write!(f, "naked single"),
+        }
+    }
+}
+
+/// A digit to place, with the cell numbered row by row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub cell: usize,
+    pub digit: usize,
+    pub technique: Technique,
+}
+
+/// How hard a puzzle is for a person.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Grade {
+    /// solved with hidden singles alone
+    Easy,
+    /// solved with hidden and naked singles
+    Medium,
+    /// singles get stuck, but little backtracking is needed after that
+    Hard,
+    /// singles get stuck and the rest needs a lot of backtracking
+    Expert,
+}
+
+impl std::fmt::Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Grade::Easy => write!(f, "easy"),
+            Grade::Medium => write!(f, "medium"),
+            Grade::Hard => write!(f, "hard"),
+            Grade::Expert => write!(f, "expert"),
+        }
+    }
+}
+
+// the cells of each row, column and box
+fn units() -> impl Iterator<Item = [usize; 9]> {
+    (0..27).map(|u| {
+        let mut cells = [0; 9];
+        for (k, cell) in cells.iter_mut().enumerate() {
+            *cell = match u {
+                0..=8 => u * 9 + k,
+                9..=17 => k * 9 + u - 9,
+                _ => (u - 18) / 3 * 27 + (u - 18) % 3 * 3 + k / 3 * 9 + k % 3,
+            };
+        }
+        cells
+    })
+}
+
+lazy_static! {
+    // the cells sharing a unit with each cell, including the cell itself
+    static ref PEERS: Vec<Vec<usize>> = (0..81)
+        .map(|cell| {
+            let mut peers: Vec<usize> = units().filter(|u| u.contains(&cell)).flatten().collect();
+            peers.sort_unstable();
+            peers.dedup();
+            peers
+        })
+        .collect();
+}
+
+/// The next single in `puzzle`, preferring hidden singles since they're
+/// easier to spot. Returns `None` if there are none.
+pub fn next_single(puzzle: &Sudoku) -> Option<Step> {
+    let cells = puzzle.cells();
+    let candidates = puzzle.candidates();
+
+    for unit in units() {
+        for digit in 1..=9 {
+            if unit.iter().any(|i| cells[*i] == digit) {
+                continue;
+            }
+            let mut places = unit.iter().filter(|i| candidates[**i].contains(&digit));
+            if let (Some(&cell), None) = (places.next(), places.next()) {
+                return Some(Step {
+                    cell,
+                    digit,
+                    technique: Technique::HiddenSingle,
+                });
+            }
+        }
+    }
+
+    candidates
+        .iter()
+        .position(|c| c.len() == 1)
+        .map(|cell| Step {
+            cell,
+            digit: candidates[cell][0],
+            technique: Technique::NakedSingle,
+        })
+}
+
+/// Grades a puzzle with a unique solution by placing singles until it's
+/// solved or they run out. When they run out, the grade depends on how many
+/// nodes the search for the rest visits beyond one per empty cell.
+pub fn grade(puzzle: &Sudoku) -> Result<Grade, Error> {
+    match sudoku_alg::solutions(puzzle.cells(), 2)?.len() {
+        0 => return Err(DlxError::NoSolutions.into()),
+        1 => {}
+        found => return Err(Error::MultipleSolutions { found }),
+    }
+
+    let mut grade = Grade::Easy;
+    let mut current = *puzzle;
+    while let Some(step) = next_single(&current) {
+        if step.technique == Technique::NakedSingle {
+            grade = Grade::Medium;
+        }
+        current
+            .set(step.cell / 9, step.cell % 9, step.digit)
+            .expect("step is inside the grid");
+    }
+
+    let empty = current.cells().iter().filter(|n| **n == 0).count();
+    if empty == 0 {
+        return Ok(grade);
+    }
+
+    let (_, stats) = sudoku_alg::solve_with_stats(current.cells().to_vec())?;
+    if stats.nodes <= 2 * empty {
+        Ok(Grade::Hard)
+    } else {
+        Ok(Grade::Expert)
+    }
+}
+
+/// Digits that, once placed, let singles alone finish a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Backdoor {
+    pub cells: Vec<usize>,
+    /// the solution's digit for each of `cells`
+    pub digits: Vec<usize>,
+}
+
+impl Backdoor {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl IntoPy<PyObject> for Backdoor {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let dict = PyDict::new(py);
+        let cells: Vec<_> = self.cells.iter().map(|i| (i / 9, i % 9)).collect();
+
+        // keys are fixed strings, so setting them can't fail
+        dict.set_item("size", self.size()).unwrap();
+        dict.set_item("cells", cells).unwrap();
+        dict.set_item("digits", self.digits).unwrap();
+
+        dict.into()
+    }
+}
+
+// places hidden and naked singles until there are none left
+fn fill_singles(cells: &mut [usize; 81]) {
+    // bit `n` is set while `n` can go in a cell
+    let mut masks = [0b11_1111_1110_u16; 81];
+    let place = |cells: &mut [usize; 81], masks: &mut [u16; 81], cell: usize, digit: usize| {
+        cells[cell] = digit;
+        for i in &PEERS[cell] {
+            masks[*i] &= !(1 << digit);
+        }
+    };
+    for cell in 0..81 {
+        if cells[cell] != 0 {
+            place(cells, &mut masks, cell, cells[cell]);
+        }
+    }
+
+    let mut progress = true;
+    while progress {
+        progress = false;
+
+        for unit in units() {
+            for digit in 1..=9 {
+                let mut places = unit
+                    .iter()
+                    .filter(|i| cells[**i] == 0 && masks[**i] & 1 << digit != 0);
+                if let (Some(&cell), None) = (places.next(), places.next()) {
+                    if unit.iter().all(|i| cells[*i] != digit) {
+                        place(cells, &mut masks, cell, digit);
+                        progress = true;
+                    }
+                }
+            }
+        }
+
+        for cell in 0..81 {
+            if cells[cell] == 0 && masks[cell].count_ones() == 1 {
+                let digit = masks[cell].trailing_zeros() as usize;
+                place(cells, &mut masks, cell, digit);
+                progress = true;
+            }
+        }
+    }
+}
+
+// the empty cells in the order the exact cover search would branch on them:
+// first those of the column it chooses (fewest remaining rows), then the
+// rest by their number of candidates
+fn branch_order(cells: &[usize; 81]) -> Result<Vec<usize>, Error> {
+    let mut events = sudoku_alg::search_events(cells)?;
+    let column = loop {
+        match events.next().transpose()? {
+            Some(Event::ChooseColumn { column, .. }) => break Some(column),
+            Some(_) => continue,
+            None => break None,
+        }
+    };
+
+    // columns are cells, then digits of rows, columns and boxes
+    let chosen: Vec<usize> = match column {
+        Some(j @ 0..=80) => vec![j],
+        Some(j) => {
+            let unit = (j - 81) / 9;
+            units()
+                .nth(unit)
+                .map(|cells| cells.to_vec())
+                .unwrap_or_default()
+        }
+        None => Vec::new(),
+    };
+
+    let candidates = Sudoku::new(*cells)?.candidates();
+    let mut order: Vec<usize> = (0..81).filter(|i| cells[*i] == 0).collect();
+    order.sort_by_key(|i| (!chosen.contains(i), candidates[*i].len()));
+
+    Ok(order)
+}
+
+// tries adding `size` more cells to `chosen`, skipping cells in `excluded`
+// so each set is only tried once
+fn find_backdoor(
+    cells: &[usize; 81],
+    solution: &[usize; 81],
+    size: usize,
+    chosen: &mut Vec<usize>,
+    excluded: &mut Vec<usize>,
+) -> Result<bool, Error> {
+    if size == 0 {
+        return Ok(!cells.contains(&0));
+    }
+
+    let depth = excluded.len();
+    for cell in branch_order(cells)? {
+        if excluded.contains(&cell) {
+            continue;
+        }
+
+        let mut next = *cells;
+        next[cell] = solution[cell];
+        fill_singles(&mut next);
+        chosen.push(cell);
+        if find_backdoor(&next, solution, size - 1, chosen, excluded)? {
+            return Ok(true);
+        }
+        chosen.pop();
+
+        // sets with this cell have all been tried at this level
+        excluded.push(cell);
+    }
+    excluded.truncate(depth);
+
+    Ok(false)
+}
+
+/// The smallest set of cells of a puzzle with a unique solution whose
+/// digits, once placed, let hidden and naked singles finish the puzzle, or
+/// `None` if it would take more than `max_size` cells. A puzzle solved by
+/// singles has an empty backdoor.
+///
+/// Sets are tried from smallest to largest. At each step, cells are tried in
+/// the order the exact cover search would branch on them after singles:
+/// first the cells of the column its minimum remaining values heuristic
+/// chooses, so the backdoor found follows the search's own guesses.
+pub fn backdoor(puzzle: &Sudoku, max_size: usize) -> Result<Option<Backdoor>, Error> {
+    let solution = match sudoku_alg::solutions(puzzle.cells(), 2)?[..] {
+        [] => return Err(DlxError::NoSolutions.into()),
+        [solution] => solution,
+        ref found => return Err(Error::MultipleSolutions { found: found.len() }),
+    };
+
+    let mut cells = *puzzle.cells();
+    fill_singles(&mut cells);
+    let empty = cells.iter().filter(|n| **n == 0).count();
+
+    for size in 0..=max_size.min(empty) {
+        let mut chosen = Vec::new();
+        if find_backdoor(&cells, &solution, size, &mut chosen, &mut Vec::new())? {
+            chosen.sort_unstable();
+            return Ok(Some(Backdoor {
+                digits: chosen.iter().map(|i| solution[*i]).collect(),
+                cells: chosen,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Python version of [`backdoor`], returning a dict with the `size`, the
+/// `cells` as `(row, col)` pairs and their `digits`, or `None`.
+#[pyfunction(max_size = "3")]
+#[pyo3(name = "backdoor")]
+pub fn py_backdoor(py: Python<'_>, puzzle: Sudoku, max_size: usize) -> PyResult<PyObject> {
+    Ok(py
+        .allow_threads(|| backdoor(&puzzle, max_size))?
+        .into_py(py))
+}
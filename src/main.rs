@@ -0,0 +1,487 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! The `sudoku` command, see `sudoku --help`.
+
+use pico_args::Arguments;
+use serde_json::{json, Value};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sudoku::batch::{self, Report};
+use sudoku::book::Book;
+use sudoku::dancing_links::Error as DlxError;
+use sudoku::formats::{self, Format, PuzzleFile};
+use sudoku::interop;
+use sudoku::logic::{self, Technique};
+use sudoku::puzzle::Sudoku;
+use sudoku::render::{self, Options};
+use sudoku::sudoku_alg;
+use sudoku::text::{self, Style};
+use sudoku::variant::Variant;
+
+const USAGE: &str = "\
+Usage: sudoku <COMMAND> [OPTIONS] [FILE...]
+
+Puzzles are read from each FILE, or from standard input if there are none or
+FILE is `-`. Files hold 81 character lines, one puzzle per line, or text grids
+separated by blank lines. Files ending in .sdk, .ss or .sdx are read in that
+format. Lines starting with `#` are ignored.
+
+Commands:
+  solve       print the solution of each puzzle
+  count       print the number of solutions of each puzzle
+  generate    print new puzzles with a unique solution
+  grade       print the difficulty of each puzzle: easy, medium, hard or expert
+  hint        print the next digit to place in each puzzle, and why
+  validate    print whether each puzzle has a unique solution, no solution,
+              several, or conflicting digits
+  convert     print each puzzle in another style or file format
+  render      draw puzzles as SVG or PNG, or as a PDF book
+  batch       solve a corpus of 81 character lines as it's read, check each
+              solution, and report throughput, times and search statistics
+
+Options:
+  --json              write one JSON object per puzzle instead of text
+  --style STYLE       how to print puzzles: line, dotted, grid, compact, boxed,
+                      pencil or ansi [default: dotted]
+  --count N           generate: number of puzzles [default: 1]
+  --seed N            generate: seed of the first puzzle [default: random]
+  --to FORMAT         convert: a style, or sdk, ss, sdx or dimacs
+  -o, --output FILE   render: file to write, ending in .svg, .png or .pdf
+  --solution          render: draw the solution
+  --pencil-marks      render: draw the candidates of empty cells
+  --title TITLE       render: title of a PDF book [default: Sudoku]
+  -h, --help          print this help
+
+Exit status is 0 on success, 1 if any puzzle failed, and 2 on usage errors.";
+
+type Error = Box<dyn std::error::Error>;
+
+struct Printer {
+    json: bool,
+    style: Style,
+    printed: usize,
+    failed: bool,
+}
+
+impl Printer {
+    fn format(&self, puzzle: &Sudoku, givens: &Sudoku) -> String {
+        text::format_cells(puzzle.cells(), Some(givens.cells()), self.style)
+            .expect("puzzle is a 9x9 sudoku")
+    }
+
+    // grids get a blank line between them
+    fn print(&mut self, text: &str, json: Value) {
+        if self.json {
+            write_line(&json.to_string());
+        } else {
+            let text = text.trim_end_matches('\n');
+            if self.printed > 0 && text.contains('\n') {
+                write_line("");
+            }
+            write_line(text);
+        }
+        self.printed += 1;
+    }
+
+    fn fail(&mut self, n: usize, puzzle: &Sudoku, err: &dyn std::fmt::Display) {
+        if self.json {
+            write_line(&json!({"puzzle": puzzle.to_line(), "error": err.to_string()}).to_string());
+        } else {
+            eprintln!("sudoku: puzzle {}: {err}", n + 1);
+        }
+        self.failed = true;
+    }
+}
+
+// like println!, but exits quietly once the reader goes away, as with `| head`
+fn write_line(text: &str) {
+    if let Err(err) = writeln!(std::io::stdout().lock(), "{text}") {
+        if err.kind() != ErrorKind::BrokenPipe {
+            eprintln!("sudoku: {err}");
+            std::process::exit(2);
+        }
+        std::process::exit(0);
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Arguments::from_env()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("sudoku: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(mut args: Arguments) -> Result<bool, Error> {
+    if args.contains(["-h", "--help"]) {
+        write_line(USAGE);
+        return Ok(true);
+    }
+
+    let command = args
+        .subcommand()?
+        .ok_or("missing command, see `sudoku --help`")?;
+    let json = args.contains("--json");
+    let style: Style = args.opt_value_from_str("--style")?.unwrap_or(Style::Dotted);
+    let mut out = Printer {
+        json,
+        style,
+        printed: 0,
+        failed: false,
+    };
+
+    match command.as_str() {
+        "generate" => {
+            let count = args.opt_value_from_str("--count")?.unwrap_or(1);
+            let seed = match args.opt_value_from_str("--seed")? {
+                Some(seed) => seed,
+                None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+            };
+            files(args)?;
+            generate(&mut out, count, seed)?;
+        }
+        "convert" => {
+            let to: String = args.value_from_str("--to")?;
+            let puzzles = read_puzzles(&files(args)?)?;
+            convert(&mut out, &puzzles, &to)?;
+        }
+        "render" => {
+            let output: PathBuf = args.value_from_str(["-o", "--output"])?;
+            let solution = args.contains("--solution");
+            let options = Options {
+                pencil_marks: args.contains("--pencil-marks"),
+                ..Options::default()
+            };
+            let title = args
+                .opt_value_from_str("--title")?
+                .unwrap_or_else(|| "Sudoku".to_owned());
+            let puzzles = read_puzzles(&files(args)?)?;
+            render(&mut out, &puzzles, &output, solution, options, &title)?;
+        }
+        "batch" => {
+            let report = match &files(args)?[..] {
+                [] => batch::solve_corpus(std::io::stdin().lock())?,
+                [file] if file == "-" => batch::solve_corpus(std::io::stdin().lock())?,
+                [file] => {
+                    let path = Path::new(file);
+                    let file = File::open(path)
+                        .map_err(|err| format!("can't read {}: {err}", path.display()))?;
+                    batch::solve_corpus(BufReader::new(file))?
+                }
+                _ => return Err("batch reads one corpus at a time".into()),
+            };
+            batch(&mut out, &report);
+        }
+        "solve" | "count" | "grade" | "hint" | "validate" => {
+            let puzzles = read_puzzles(&files(args)?)?;
+            for (n, puzzle) in puzzles.iter().enumerate() {
+                match command.as_str() {
+                    "solve" => solve(&mut out, n, puzzle),
+                    "count" => count(&mut out, n, puzzle),
+                    "grade" => grade(&mut out, n, puzzle),
+                    "hint" => hint(&mut out, n, puzzle),
+                    _ => validate(&mut out, n, puzzle),
+                }
+            }
+        }
+        _ => return Err(format!("unknown command {command:?}, see `sudoku --help`").into()),
+    }
+
+    Ok(!out.failed)
+}
+
+// the arguments left once options are taken
+fn files(args: Arguments) -> Result<Vec<OsString>, Error> {
+    let files = args.finish();
+
+    match files.iter().find(|f| f.to_string_lossy().starts_with("--")) {
+        Some(flag) => Err(format!("unknown option {flag:?}, see `sudoku --help`").into()),
+        None => Ok(files),
+    }
+}
+
+fn read_puzzles(files: &[OsString]) -> Result<Vec<Sudoku>, Error> {
+    let mut puzzles = Vec::new();
+
+    if files.is_empty() {
+        return read_stdin();
+    }
+    for file in files {
+        let path = Path::new(file);
+        if file == "-" {
+            puzzles.extend(read_stdin()?);
+        } else if Format::from_path(path).is_ok() {
+            puzzles.push(formats::load(path)?.current());
+        } else {
+            let text = std::fs::read_to_string(path)
+                .map_err(|err| format!("can't read {}: {err}", path.display()))?;
+            puzzles.extend(parse_puzzles(&text)?);
+        }
+    }
+
+    Ok(puzzles)
+}
+
+fn read_stdin() -> Result<Vec<Sudoku>, Error> {
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+
+    parse_puzzles(&text)
+}
+
+// blocks separated by blank lines are either one grid, or lines that each
+// hold a puzzle
+fn parse_puzzles(text: &str) -> Result<Vec<Sudoku>, Error> {
+    let mut puzzles = Vec::new();
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with('#'))
+        .collect();
+
+    for block in lines.split(|(_, line)| line.trim().is_empty()) {
+        let Some((_, first)) = block.first() else {
+            continue;
+        };
+        if text::parse(first).is_err() {
+            let grid: Vec<&str> = block.iter().map(|(_, line)| *line).collect();
+            puzzles.push(text::parse(&grid.join("\n"))?);
+            continue;
+        }
+        for (i, line) in block {
+            puzzles.push(text::parse(line).map_err(|err| format!("line {}: {err}", i + 1))?);
+        }
+    }
+
+    Ok(puzzles)
+}
+
+fn solve(out: &mut Printer, n: usize, puzzle: &Sudoku) {
+    // stop at the second solution, so puzzles with many don't hang
+    let solution = sudoku_alg::solutions(puzzle.cells(), 2).and_then(|found| match found[..] {
+        [solution] => Ok(Sudoku::new(solution)?),
+        [] => Err(DlxError::NoSolutions.into()),
+        _ => Err(sudoku_alg::Error::MultipleSolutions { found: found.len() }),
+    });
+
+    match solution {
+        Ok(solution) => out.print(
+            &out.format(&solution, puzzle),
+            json!({"puzzle": puzzle.to_line(), "solution": solution.to_line()}),
+        ),
+        Err(err) => out.fail(n, puzzle, &err),
+    }
+}
+
+fn count(out: &mut Printer, n: usize, puzzle: &Sudoku) {
+    match sudoku_alg::count_solutions(puzzle.cells(), 2, None) {
+        Ok(count) => out.print(
+            &count.to_string(),
+            json!({"puzzle": puzzle.to_line(), "solutions": count}),
+        ),
+        Err(err) => out.fail(n, puzzle, &err),
+    }
+}
+
+fn generate(out: &mut Printer, count: u64, seed: u64) -> Result<(), Error> {
+    for seed in (0..count).map(|k| seed.wrapping_add(k)) {
+        let puzzle = Sudoku::new(sudoku_alg::generate(seed)?)?;
+        out.print(
+            &out.format(&puzzle, &puzzle),
+            json!({"seed": seed, "puzzle": puzzle.to_line()}),
+        );
+    }
+
+    Ok(())
+}
+
+fn grade(out: &mut Printer, n: usize, puzzle: &Sudoku) {
+    match logic::grade(puzzle) {
+        Ok(grade) => out.print(
+            &grade.to_string(),
+            json!({"puzzle": puzzle.to_line(), "grade": grade.to_string()}),
+        ),
+        Err(err) => out.fail(n, puzzle, &err),
+    }
+}
+
+// a single if there is one, or else the solution's digit for the cell with
+// the fewest candidates
+fn hint(out: &mut Printer, n: usize, puzzle: &Sudoku) {
+    if !puzzle.is_valid() {
+        return out.fail(n, puzzle, &"digits conflict!");
+    }
+    if !puzzle.cells().contains(&0) {
+        return out.fail(n, puzzle, &"puzzle is already solved!");
+    }
+
+    let (cell, digit, technique) = match logic::next_single(puzzle) {
+        Some(step) => (step.cell, step.digit, Some(step.technique)),
+        None => {
+            let solution = match puzzle.solve() {
+                Ok(solution) => solution,
+                Err(err) => return out.fail(n, puzzle, &err),
+            };
+            let candidates = puzzle.candidates();
+            let cell = (0..81)
+                .filter(|i| puzzle.cells()[*i] == 0)
+                .min_by_key(|i| candidates[*i].len())
+                .expect("puzzle has an empty cell");
+            (cell, solution.cells()[cell], None)
+        }
+    };
+
+    let (reason, name) = match technique {
+        Some(Technique::HiddenSingle) => ("hidden single", "hidden_single"),
+        Some(Technique::NakedSingle) => ("naked single", "naked_single"),
+        None => ("from the solution", "solution"),
+    };
+    out.print(
+        &format!("r{}c{} = {digit} ({reason})", cell / 9 + 1, cell % 9 + 1),
+        json!({
+            "puzzle": puzzle.to_line(),
+            "cell": cell,
+            "digit": digit,
+            "technique": name,
+        }),
+    );
+}
+
+fn validate(out: &mut Printer, n: usize, puzzle: &Sudoku) {
+    let status = match puzzle.is_valid() {
+        false => "conflict",
+        true => match sudoku_alg::solutions(puzzle.cells(), 2) {
+            Ok(solutions) => match solutions.len() {
+                0 => "no solution",
+                1 => "unique",
+                _ => "multiple solutions",
+            },
+            Err(err) => return out.fail(n, puzzle, &err),
+        },
+    };
+
+    out.failed |= status != "unique";
+    out.print(
+        status,
+        json!({
+            "puzzle": puzzle.to_line(),
+            "valid": status == "unique",
+            "status": status.replace(' ', "_"),
+        }),
+    );
+}
+
+fn convert(out: &mut Printer, puzzles: &[Sudoku], to: &str) -> Result<(), Error> {
+    for puzzle in puzzles {
+        let text = match to.parse::<Format>() {
+            Ok(format) => formats::write(&PuzzleFile::new(*puzzle), format),
+            Err(_) if to == "dimacs" => interop::to_dimacs(puzzle),
+            Err(_) => text::format(puzzle, to.parse()?),
+        };
+        out.print(&text, json!({"puzzle": puzzle.to_line(), "output": text}));
+    }
+
+    Ok(())
+}
+
+fn batch(out: &mut Printer, report: &Report) {
+    let ms = |percent: f64| report.percentile(percent).as_secs_f64() * 1000.0;
+    let failures: Vec<Value> = report
+        .failures
+        .iter()
+        .map(|failure| json!({"line": failure.line, "error": failure.msg}))
+        .collect();
+
+    out.print(
+        &report.to_string(),
+        json!({
+            "puzzles": report.puzzles,
+            "solved": report.puzzles - report.failures.len(),
+            "failures": failures,
+            "seconds": report.total().as_secs_f64(),
+            "throughput": report.throughput(),
+            "p50_ms": ms(50.0),
+            "p90_ms": ms(90.0),
+            "p99_ms": ms(99.0),
+            "max_ms": ms(100.0),
+            "nodes": report.nodes,
+            "updates": report.updates,
+            "max_depth": report.max_depth,
+        }),
+    );
+    out.failed |= !report.failures.is_empty();
+}
+
+fn render(
+    out: &mut Printer,
+    puzzles: &[Sudoku],
+    output: &Path,
+    solution: bool,
+    options: Options,
+    title: &str,
+) -> Result<(), Error> {
+    let extension = output
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let bytes = match extension.as_str() {
+        "pdf" => {
+            let mut book = Book::new(title);
+            book.options = options;
+            for puzzle in puzzles {
+                book.add(Variant::new(*puzzle), None, None);
+            }
+            book.pdf()?
+        }
+        "svg" | "png" => {
+            let puzzle = match puzzles {
+                [puzzle] => Variant::new(*puzzle),
+                _ => {
+                    let got = puzzles.len();
+                    return Err(format!("SVG and PNG hold one puzzle, got {got}").into());
+                }
+            };
+            let filled = match solution {
+                true => puzzle.solve()?,
+                false => Sudoku::default(),
+            };
+            match extension.as_str() {
+                "svg" => render::svg(&puzzle, &filled, &options).into_bytes(),
+                _ => render::png(&puzzle, &filled, &options)?,
+            }
+        }
+        _ => return Err(format!("can't tell the format of {}", output.display()).into()),
+    };
+
+    std::fs::write(output, bytes)
+        .map_err(|err| format!("can't write {}: {err}", output.display()))?;
+    if out.json {
+        out.print(
+            "",
+            json!({"output": output.display().to_string(), "puzzles": puzzles.len()}),
+        );
+    }
+
+    Ok(())
+}
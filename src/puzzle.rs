@@ -0,0 +1,454 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use crate::arrays;
+use crate::canonical;
+use crate::sudoku_alg::{self, Error};
+use crate::text::{self, Style};
+use pyo3::{
+    basic::CompareOp,
+    exceptions::PyIndexError,
+    prelude::*,
+    types::{PyString, PyTuple},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A 9x9 sudoku grid, stored row by row with 0 for empty cells.
+#[pyclass(module = "sudoku")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<usize>", into = "Vec<usize>"))]
+pub struct Sudoku {
+    cells: [usize; 81],
+}
+
+impl Sudoku {
+    pub fn new(cells: [usize; 81]) -> Result<Self, Error> {
+        match cells.iter().find(|n| **n > 9) {
+            Some(n) => Err(Error::InvalidCell { got: *n }),
+            None => Ok(Self { cells }),
+        }
+    }
+
+    pub fn from_slice(cells: &[usize]) -> Result<Self, Error> {
+        let cells: [usize; 81] = cells
+            .try_into()
+            .map_err(|_| Error::InvalidGrid { got: cells.len() })?;
+
+        Self::new(cells)
+    }
+
+    pub fn from_rows(rows: &[Vec<usize>]) -> Result<Self, Error> {
+        if rows.len() != 9 || rows.iter().any(|row| row.len() != 9) {
+            return Err(Error::InvalidGrid {
+                got: rows.iter().map(|row| row.len()).sum(),
+            });
+        }
+
+        Self::from_slice(&rows.concat())
+    }
+
+    pub fn cells(&self) -> &[usize; 81] {
+        &self.cells
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<usize> {
+        (row < 9 && col < 9).then(|| self.cells[row * 9 + col])
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, n: usize) -> Result<(), Error> {
+        if row >= 9 || col >= 9 {
+            return Err(Error::InvalidPosition { row, col });
+        }
+        if n > 9 {
+            return Err(Error::InvalidCell { got: n });
+        }
+
+        self.cells[row * 9 + col] = n;
+
+        Ok(())
+    }
+
+    pub fn solve(&self) -> Result<Self, Error> {
+        Ok(Self {
+            cells: sudoku_alg::solve(self.cells.to_vec())?,
+        })
+    }
+
+    /// Returns true if no digit appears twice in a row, column or box. This
+    /// doesn't check that the puzzle has a solution.
+    pub fn is_valid(&self) -> bool {
+        (0..81).all(|i| {
+            let n = self.cells[i];
+            n == 0 || peers(i).all(|j| self.cells[j] != n)
+        })
+    }
+
+    /// Digits that can be placed in each empty cell without conflicting with
+    /// its row, column or box. Filled cells have no candidates.
+    pub fn candidates(&self) -> Vec<Vec<usize>> {
+        (0..81)
+            .map(|i| {
+                if self.cells[i] != 0 {
+                    return Vec::new();
+                }
+
+                let mut used = [false; 10];
+                for j in peers(i) {
+                    used[self.cells[j]] = true;
+                }
+
+                (1..=9).filter(|n| !used[*n]).collect()
+            })
+            .collect()
+    }
+
+    pub fn to_line(&self) -> String {
+        text::format(self, Style::Dotted)
+    }
+
+    // the puzzle with each cell taken from the one `source` gives, as a
+    // (row, col) pair
+    fn map_cells(&self, source: impl Fn(usize, usize) -> (usize, usize)) -> Self {
+        let mut cells = [0; 81];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            let (row, col) = source(i / 9, i % 9);
+            *cell = self.cells[row * 9 + col];
+        }
+
+        Self { cells }
+    }
+
+    /// Rotates the grid clockwise by `turns` quarter turns.
+    pub fn rotate(&self, turns: usize) -> Self {
+        match turns % 4 {
+            0 => *self,
+            1 => self.map_cells(|r, c| (8 - c, r)),
+            2 => self.map_cells(|r, c| (8 - r, 8 - c)),
+            _ => self.map_cells(|r, c| (c, 8 - r)),
+        }
+    }
+
+    /// Swaps rows and columns, mirroring the grid on its main diagonal.
+    pub fn transpose(&self) -> Self {
+        self.map_cells(|r, c| (c, r))
+    }
+
+    /// Reverses the order of the columns.
+    pub fn mirror_left_right(&self) -> Self {
+        self.map_cells(|r, c| (r, 8 - c))
+    }
+
+    /// Reverses the order of the rows.
+    pub fn mirror_top_bottom(&self) -> Self {
+        self.map_cells(|r, c| (8 - r, c))
+    }
+
+    /// Replaces each digit `n` with `digits[n - 1]`, which must be a
+    /// permutation of 1 to 9.
+    pub fn permute_digits(&self, digits: &[usize]) -> Result<Self, Error> {
+        let mut sorted = digits.to_vec();
+        sorted.sort_unstable();
+        if sorted != [1, 2, 3, 4, 5, 6, 7, 8, 9] {
+            return Err(Error::InvalidPermutation {
+                got: digits.to_vec(),
+            });
+        }
+
+        let mut cells = self.cells;
+        for n in cells.iter_mut().filter(|n| **n != 0) {
+            *n = digits[*n - 1];
+        }
+
+        Ok(Self { cells })
+    }
+
+    /// Reorders the rows of a band, from 0 at the top, so that its `i`th row
+    /// is the one that was `order[i]`th.
+    pub fn permute_rows(&self, band: usize, order: &[usize]) -> Result<Self, Error> {
+        let order = order3(order)?;
+        if band > 2 {
+            return Err(Error::InvalidBand { got: band });
+        }
+
+        Ok(self.map_cells(|r, c| match r / 3 == band {
+            true => (band * 3 + order[r % 3], c),
+            false => (r, c),
+        }))
+    }
+
+    /// Reorders the columns of a stack, from 0 on the left, like
+    /// [`Sudoku::permute_rows`].
+    pub fn permute_columns(&self, stack: usize, order: &[usize]) -> Result<Self, Error> {
+        Ok(self.transpose().permute_rows(stack, order)?.transpose())
+    }
+
+    /// Reorders the bands so that the `i`th is the one that was `order[i]`th.
+    pub fn permute_bands(&self, order: &[usize]) -> Result<Self, Error> {
+        let order = order3(order)?;
+
+        Ok(self.map_cells(|r, c| (order[r / 3] * 3 + r % 3, c)))
+    }
+
+    /// Reorders the stacks like [`Sudoku::permute_bands`].
+    pub fn permute_stacks(&self, order: &[usize]) -> Result<Self, Error> {
+        Ok(self.transpose().permute_bands(order)?.transpose())
+    }
+
+    /// An equivalent puzzle made by a random combination of the
+    /// transformations above. The same seed always gives the same puzzle.
+    pub fn random_isomorph(&self, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut shuffled = |n: usize| {
+            let mut order: Vec<usize> = (0..n).collect();
+            order.shuffle(&mut rng);
+            order
+        };
+
+        let digits: Vec<usize> = shuffled(9).iter().map(|n| n + 1).collect();
+        let mut puzzle = self
+            .permute_digits(&digits)
+            .and_then(|p| p.permute_bands(&shuffled(3)))
+            .and_then(|p| p.permute_stacks(&shuffled(3)));
+        for i in 0..3 {
+            puzzle = puzzle
+                .and_then(|p| p.permute_rows(i, &shuffled(3)))
+                .and_then(|p| p.permute_columns(i, &shuffled(3)));
+        }
+        let puzzle = puzzle.expect("permutations are shuffled ranges");
+
+        match rng.gen() {
+            true => puzzle.transpose(),
+            false => puzzle,
+        }
+    }
+}
+
+// checks an order of the three rows, columns, bands or stacks
+fn order3(order: &[usize]) -> Result<[usize; 3], Error> {
+    let mut sorted = order.to_vec();
+    sorted.sort_unstable();
+
+    match order.try_into() {
+        Ok(order) if sorted == [0, 1, 2] => Ok(order),
+        _ => Err(Error::InvalidPermutation {
+            got: order.to_vec(),
+        }),
+    }
+}
+
+impl TryFrom<Vec<usize>> for Sudoku {
+    type Error = Error;
+
+    fn try_from(cells: Vec<usize>) -> Result<Self, Self::Error> {
+        Self::from_slice(&cells)
+    }
+}
+
+impl From<Sudoku> for Vec<usize> {
+    fn from(puzzle: Sudoku) -> Self {
+        puzzle.cells.to_vec()
+    }
+}
+
+impl Default for Sudoku {
+    /// An empty grid.
+    fn default() -> Self {
+        Self { cells: [0; 81] }
+    }
+}
+
+impl std::str::FromStr for Sudoku {
+    type Err = text::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse(s)
+    }
+}
+
+impl std::fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // cells are validated on construction, so formatting can't fail
+        let out = sudoku_alg::format_puzzle(&self.cells).map_err(|_| std::fmt::Error)?;
+
+        write!(f, "{out}")
+    }
+}
+
+// cells sharing a row, column or box with cell `i`, excluding `i` itself
+fn peers(i: usize) -> impl Iterator<Item = usize> {
+    let (r, c) = (i / 9, i % 9);
+    let (br, bc) = (r / 3 * 3, c / 3 * 3);
+
+    (0..81).filter(move |j| {
+        let (jr, jc) = (j / 9, j % 9);
+        *j != i && (jr == r || jc == c || (jr / 3 * 3 == br && jc / 3 * 3 == bc))
+    })
+}
+
+#[pymethods]
+impl Sudoku {
+    /// Accepts 81 cells as a flat list, a 9x9 nested list, a NumPy array of
+    /// either shape, or a string in any format understood by `parse`.
+    #[new]
+    fn py_new(cells: &PyAny) -> PyResult<Self> {
+        if arrays::is_array(cells) {
+            return Ok(Self::from_slice(&arrays::read_puzzle(cells)?.cells)?);
+        }
+        if let Ok(line) = cells.downcast::<PyString>() {
+            return Ok(text::parse(line.to_str()?)?);
+        }
+        if let Ok(rows) = cells.extract::<Vec<Vec<usize>>>() {
+            return Ok(Self::from_rows(&rows)?);
+        }
+
+        Ok(Self::from_slice(&cells.extract::<Vec<usize>>()?)?)
+    }
+
+    fn __getitem__(&self, index: (usize, usize)) -> PyResult<usize> {
+        self.get(index.0, index.1)
+            .ok_or_else(|| PyIndexError::new_err(format!("cell {index:?} is out of range")))
+    }
+
+    /// A copy with `(row, col)` set to `n`. Puzzles are immutable so that
+    /// their hash can't change while they're in a set or a dict.
+    fn with_cell(&self, row: usize, col: usize, n: usize) -> PyResult<Self> {
+        let mut puzzle = *self;
+        match puzzle.set(row, col, n) {
+            Err(Error::InvalidPosition { .. }) => Err(PyIndexError::new_err(format!(
+                "cell {:?} is out of range",
+                (row, col)
+            ))),
+            result => Ok(result.map(|_| puzzle)?),
+        }
+    }
+
+    #[pyo3(name = "solve")]
+    fn py_solve(&self, py: Python<'_>) -> PyResult<Self> {
+        Ok(py.allow_threads(|| self.solve())?)
+    }
+
+    #[pyo3(name = "is_valid")]
+    fn py_is_valid(&self) -> bool {
+        self.is_valid()
+    }
+
+    #[pyo3(name = "candidates")]
+    fn py_candidates(&self) -> Vec<Vec<Vec<usize>>> {
+        self.candidates()
+            .chunks(9)
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    /// The canonical form of the puzzle, equal for every puzzle that's
+    /// a relabeling, transposition or row and column permutation of it.
+    fn canonical(&self, py: Python<'_>) -> Self {
+        py.allow_threads(|| canonical::canonical_form(self))
+    }
+
+    #[pyo3(name = "rotate")]
+    #[args(turns = "1")]
+    fn py_rotate(&self, turns: usize) -> Self {
+        self.rotate(turns)
+    }
+
+    #[pyo3(name = "transpose")]
+    fn py_transpose(&self) -> Self {
+        self.transpose()
+    }
+
+    #[pyo3(name = "mirror_left_right")]
+    fn py_mirror_left_right(&self) -> Self {
+        self.mirror_left_right()
+    }
+
+    #[pyo3(name = "mirror_top_bottom")]
+    fn py_mirror_top_bottom(&self) -> Self {
+        self.mirror_top_bottom()
+    }
+
+    #[pyo3(name = "permute_digits")]
+    fn py_permute_digits(&self, digits: Vec<usize>) -> PyResult<Self> {
+        Ok(self.permute_digits(&digits)?)
+    }
+
+    #[pyo3(name = "permute_rows")]
+    fn py_permute_rows(&self, band: usize, order: Vec<usize>) -> PyResult<Self> {
+        Ok(self.permute_rows(band, &order)?)
+    }
+
+    #[pyo3(name = "permute_columns")]
+    fn py_permute_columns(&self, stack: usize, order: Vec<usize>) -> PyResult<Self> {
+        Ok(self.permute_columns(stack, &order)?)
+    }
+
+    #[pyo3(name = "permute_bands")]
+    fn py_permute_bands(&self, order: Vec<usize>) -> PyResult<Self> {
+        Ok(self.permute_bands(&order)?)
+    }
+
+    #[pyo3(name = "permute_stacks")]
+    fn py_permute_stacks(&self, order: Vec<usize>) -> PyResult<Self> {
+        Ok(self.permute_stacks(&order)?)
+    }
+
+    #[pyo3(name = "random_isomorph")]
+    fn py_random_isomorph(&self, seed: u64) -> Self {
+        self.random_isomorph(seed)
+    }
+
+    #[pyo3(name = "to_list")]
+    fn py_to_list(&self) -> Vec<usize> {
+        self.cells.to_vec()
+    }
+
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Sudoku('{}')", self.to_line())
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyObject {
+        match op {
+            CompareOp::Eq => (self == other).into_py(py),
+            CompareOp::Ne => (self != other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn __copy__(&self) -> Self {
+        *self
+    }
+
+    fn __deepcopy__(&self, _memo: &PyAny) -> Self {
+        *self
+    }
+
+    fn __reduce__(&self, py: Python<'_>) -> (PyObject, Py<PyTuple>) {
+        let args = PyTuple::new(py, [self.py_to_list()]).into();
+
+        (py.get_type::<Self>().into(), args)
+    }
+}
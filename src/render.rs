@@ -0,0 +1,537 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+//! Draws puzzles as SVG, and as PNG by rasterizing the SVG with resvg.
+
+use crate::puzzle::Sudoku;
+use crate::variant::{DotKind, Variant};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use resvg::{tiny_skia, usvg};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    InvalidSvg { msg: String },
+    InvalidSize { width: u32, height: u32 },
+    InvalidFont { path: String },
+    Png { msg: String },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidSvg { msg } => write!(f, "can't parse rendered SVG: {msg}!"),
+            Error::InvalidSize { width, height } => {
+                write!(f, "invalid image size: {width}x{height}!")
+            }
+            Error::InvalidFont { path } => write!(f, "can't load font file {path:?}!"),
+            Error::Png { msg } => write!(f, "can't encode PNG: {msg}!"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyErr::new::<PyValueError, _>(err.to_string())
+    }
+}
+
+/// How a puzzle is drawn. Colours are any SVG colour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// width of a cell in pixels
+    pub cell_size: f64,
+    pub font_family: String,
+    /// extra font to make available when rasterizing, besides system fonts
+    pub font_file: Option<PathBuf>,
+    pub given_color: String,
+    /// colour of digits that aren't givens, such as those of a solution
+    pub filled_color: String,
+    pub highlight_color: String,
+    /// cells to shade with `highlight_color`, numbered row by row
+    pub highlights: Vec<usize>,
+    /// draw the candidates of each empty cell in small digits
+    pub pencil_marks: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            cell_size: 48.0,
+            font_family: "sans-serif".to_owned(),
+            font_file: None,
+            given_color: "#000000".to_owned(),
+            filled_color: "#1f5fbf".to_owned(),
+            highlight_color: "#fff3a0".to_owned(),
+            highlights: Vec::new(),
+            pencil_marks: false,
+        }
+    }
+}
+
+// centre of cell `i`
+fn center(i: usize, size: f64) -> (f64, f64) {
+    (((i % 9) as f64 + 0.5) * size, ((i / 9) as f64 + 0.5) * size)
+}
+
+fn points(cells: &[usize], size: f64) -> String {
+    let points: Vec<String> = cells
+        .iter()
+        .map(|i| {
+            let (x, y) = center(*i, size);
+            format!("{x:.1},{y:.1}")
+        })
+        .collect();
+
+    points.join(" ")
+}
+
+/// Escapes text for use in SVG.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Width and height of the image drawn by [`svg`].
+pub fn size(options: &Options) -> f64 {
+    9.5 * options.cell_size
+}
+
+/// Draws `variant` with the digits of `filled` that aren't givens, which may
+/// be a solution or a partly solved grid. Cages, thermos, arrows, dots,
+/// diagonals and irregular regions are drawn as decorations.
+pub fn svg(variant: &Variant, filled: &Sudoku, options: &Options) -> String {
+    let width = size(options);
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{width:.0}\" viewBox=\"0 0 {width:.1} {width:.1}\">\n{}</svg>\n",
+        contents(variant, filled, options)
+    )
+}
+
+// the elements of `svg`, so they can be placed inside other drawings
+pub(crate) fn contents(variant: &Variant, filled: &Sudoku, options: &Options) -> String {
+    let s = options.cell_size;
+    let margin = s / 4.0;
+    let width = size(options);
+    let givens = variant.givens.cells();
+    let mut out = String::new();
+
+    let mut line = |text: String| {
+        out += &text;
+        out.push('\n');
+    };
+
+    line(format!(
+        r#"<rect width="{width:.1}" height="{width:.1}" fill="white"/>"#
+    ));
+    line(format!(
+        r#"<g transform="translate({margin:.1},{margin:.1})" font-family="{}">"#,
+        escape(&options.font_family)
+    ));
+
+    for i in &options.highlights {
+        if *i < 81 {
+            let (x, y) = ((i % 9) as f64 * s, (i / 9) as f64 * s);
+            line(format!(
+                r#"<rect x="{x:.1}" y="{y:.1}" width="{s:.1}" height="{s:.1}" fill="{}"/>"#,
+                escape(&options.highlight_color)
+            ));
+        }
+    }
+
+    // diagonals and thermos sit under the grid lines
+    if variant.positive_diagonal {
+        line(format!(
+            r##"<line x1="0" y1="{0:.1}" x2="{0:.1}" y2="0" stroke="#999999" stroke-width="{1:.1}"/>"##,
+            9.0 * s,
+            s / 24.0
+        ));
+    }
+    if variant.negative_diagonal {
+        line(format!(
+            r##"<line x1="0" y1="0" x2="{0:.1}" y2="{0:.1}" stroke="#999999" stroke-width="{1:.1}"/>"##,
+            9.0 * s,
+            s / 24.0
+        ));
+    }
+    for thermo in &variant.thermos {
+        if let Some(bulb) = thermo.first() {
+            let (x, y) = center(*bulb, s);
+            line(format!(
+                r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="#cccccc"/>"##,
+                s * 0.4
+            ));
+            line(format!(
+                r##"<polyline points="{}" fill="none" stroke="#cccccc" stroke-width="{:.1}" stroke-linecap="round" stroke-linejoin="round"/>"##,
+                points(thermo, s),
+                s * 0.3
+            ));
+        }
+    }
+
+    // thin lines between cells, thick ones between regions
+    line(format!(
+        r#"<g stroke="black" stroke-linecap="square" stroke-width="{:.1}">"#,
+        s / 48.0
+    ));
+    for i in 0..81 {
+        let (r, c) = (i / 9, i % 9);
+        let (x, y) = (c as f64 * s, r as f64 * s);
+
+        if c < 8 {
+            let thick = variant.regions[i] != variant.regions[i + 1];
+            line(format!(
+                r#"<line x1="{0:.1}" y1="{y:.1}" x2="{0:.1}" y2="{1:.1}"{2}/>"#,
+                x + s,
+                y + s,
+                if thick { r#" stroke-width="3""# } else { "" }
+            ));
+        }
+        if r < 8 {
+            let thick = variant.regions[i] != variant.regions[i + 9];
+            line(format!(
+                r#"<line x1="{x:.1}" y1="{0:.1}" x2="{1:.1}" y2="{0:.1}"{2}/>"#,
+                y + s,
+                x + s,
+                if thick { r#" stroke-width="3""# } else { "" }
+            ));
+        }
+    }
+    line("</g>".to_owned());
+    line(format!(
+        r#"<rect width="{0:.1}" height="{0:.1}" fill="none" stroke="black" stroke-width="3"/>"#,
+        9.0 * s
+    ));
+
+    // cages are dashed outlines inset into their cells
+    let inset = s * 0.08;
+    for cage in &variant.cages {
+        line(format!(
+            r#"<g stroke="black" stroke-width="1" stroke-dasharray="{0:.1},{0:.1}">"#,
+            s / 12.0
+        ));
+        for i in &cage.cells {
+            let (x, y) = ((i % 9) as f64 * s, (i / 9) as f64 * s);
+            let inside = |j: Option<usize>| j.is_some_and(|j| cage.cells.contains(&j));
+            let (x1, y1, x2, y2) = (x + inset, y + inset, x + s - inset, y + s - inset);
+
+            if !inside((i / 9 > 0).then(|| i - 9)) {
+                line(format!(
+                    r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y1:.1}"/>"#
+                ));
+            }
+            if !inside((i / 9 < 8).then(|| i + 9)) {
+                line(format!(
+                    r#"<line x1="{x1:.1}" y1="{y2:.1}" x2="{x2:.1}" y2="{y2:.1}"/>"#
+                ));
+            }
+            if !inside((i % 9 > 0).then(|| i - 1)) {
+                line(format!(
+                    r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x1:.1}" y2="{y2:.1}"/>"#
+                ));
+            }
+            if !inside((i % 9 < 8).then(|| i + 1)) {
+                line(format!(
+                    r#"<line x1="{x2:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}"/>"#
+                ));
+            }
+        }
+        line("</g>".to_owned());
+
+        // the sum goes in the top left cell
+        if let (Some(sum), Some(first)) = (cage.sum, cage.cells.iter().min()) {
+            let (x, y) = ((first % 9) as f64 * s, (first / 9) as f64 * s);
+            line(format!(
+                r#"<text x="{:.1}" y="{:.1}" font-size="{:.1}" fill="black">{sum}</text>"#,
+                x + inset * 1.2,
+                y + inset + s * 0.18,
+                s * 0.2
+            ));
+        }
+    }
+
+    for arrow in &variant.arrows {
+        // an arrow without a bulb has nowhere to start from
+        let Some(&bulb) = arrow.bulb.first() else {
+            continue;
+        };
+        let (x, y) = center(bulb, s);
+        line(format!(
+            r##"<circle cx="{x:.1}" cy="{y:.1}" r="{:.1}" fill="none" stroke="#888888" stroke-width="2"/>"##,
+            s * 0.4
+        ));
+        for path in &arrow.lines {
+            let mut cells = vec![bulb];
+            cells.extend(path);
+            line(format!(
+                r##"<polyline points="{}" fill="none" stroke="#888888" stroke-width="2" stroke-linejoin="round"/>"##,
+                points(&cells, s)
+            ));
+
+            // arrowhead pointing along the last segment
+            if let [.., a, b] = cells[..] {
+                let ((ax, ay), (bx, by)) = (center(a, s), center(b, s));
+                let angle = (by - ay).atan2(bx - ax);
+                let head = s * 0.2;
+                let corner = |turn: f64| {
+                    let t = angle + std::f64::consts::PI + turn;
+                    format!("{:.1},{:.1}", bx + head * t.cos(), by + head * t.sin())
+                };
+                line(format!(
+                    r##"<polyline points="{} {bx:.1},{by:.1} {}" fill="none" stroke="#888888" stroke-width="2"/>"##,
+                    corner(0.5),
+                    corner(-0.5)
+                ));
+            }
+        }
+    }
+
+    for dot in &variant.dots {
+        let ((ax, ay), (bx, by)) = (center(dot.cells.0, s), center(dot.cells.1, s));
+        let fill = match dot.kind {
+            DotKind::Difference => "white",
+            DotKind::Ratio => "black",
+        };
+        line(format!(
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{fill}" stroke="black" stroke-width="1"/>"#,
+            (ax + bx) / 2.0,
+            (ay + by) / 2.0,
+            s * 0.1
+        ));
+    }
+
+    // digits, then pencil marks in the cells still empty
+    let current: Vec<usize> = givens
+        .iter()
+        .zip(filled.cells())
+        .map(|(g, f)| if *g != 0 { *g } else { *f })
+        .collect();
+    for (i, n) in current.iter().enumerate() {
+        if *n == 0 {
+            continue;
+        }
+        let (x, y) = center(i, s);
+        let color = escape(if givens[i] != 0 {
+            &options.given_color
+        } else {
+            &options.filled_color
+        });
+        line(format!(
+            r#"<text x="{x:.1}" y="{:.1}" font-size="{:.1}" text-anchor="middle" fill="{color}">{n}</text>"#,
+            y + s * 0.22,
+            s * 0.62
+        ));
+    }
+
+    if options.pencil_marks {
+        let grid = Sudoku::from_slice(&current).expect("cells come from valid puzzles");
+        for (i, candidates) in grid.candidates().iter().enumerate() {
+            let (x, y) = ((i % 9) as f64 * s, (i / 9) as f64 * s);
+            for n in candidates {
+                let (r, c) = ((n - 1) / 3, (n - 1) % 3);
+                line(format!(
+                    r##"<text x="{:.1}" y="{:.1}" font-size="{:.1}" text-anchor="middle" fill="#666666">{n}</text>"##,
+                    x + (c as f64 + 0.5) * s / 3.0,
+                    y + (r as f64 + 0.8) * s / 3.0,
+                    s * 0.24
+                ));
+            }
+        }
+    }
+
+    line("</g>".to_owned());
+
+    out
+}
+
+lazy_static! {
+    // scanning the system for fonts is slow, so it's only done once
+    static ref SYSTEM_FONTS: Arc<usvg::fontdb::Database> = {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        set_fallback_families(&mut fonts);
+        Arc::new(fonts)
+    };
+}
+
+// generic families map to fonts like Arial, which many systems lack
+fn set_fallback_families(fonts: &mut usvg::fontdb::Database) {
+    let query = usvg::fontdb::Query {
+        families: &[usvg::fontdb::Family::SansSerif],
+        ..Default::default()
+    };
+    if fonts.query(&query).is_none() {
+        let families: Vec<String> = fonts
+            .faces()
+            .filter_map(|face| face.families.first().map(|(family, _)| family.clone()))
+            .collect();
+        let fallback = families
+            .iter()
+            .find(|family| family.contains("Sans") && !family.contains("Mono"))
+            .or(families.first());
+        if let Some(family) = fallback.cloned() {
+            fonts.set_sans_serif_family(family.clone());
+            fonts.set_serif_family(family);
+        }
+    }
+}
+
+// parses SVG drawn by this crate, with system fonts and `font_file` loaded
+pub(crate) fn parse(svg: &str, options: &Options) -> Result<usvg::Tree, Error> {
+    let mut opt = usvg::Options {
+        font_family: options.font_family.clone(),
+        fontdb: SYSTEM_FONTS.clone(),
+        ..usvg::Options::default()
+    };
+    // the shared database is only copied when a font file is added to it
+    if let Some(path) = &options.font_file {
+        let fonts = opt.fontdb_mut();
+        fonts.load_font_file(path).map_err(|_| Error::InvalidFont {
+            path: path.display().to_string(),
+        })?;
+        set_fallback_families(fonts);
+    }
+
+    usvg::Tree::from_str(svg, &opt).map_err(|err| Error::InvalidSvg {
+        msg: err.to_string(),
+    })
+}
+
+/// Rasterizes [`svg`] into PNG bytes.
+pub fn png(variant: &Variant, filled: &Sudoku, options: &Options) -> Result<Vec<u8>, Error> {
+    let tree = parse(&svg(variant, filled, options), options)?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).ok_or(Error::InvalidSize {
+            width: size.width(),
+            height: size.height(),
+        })?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap.encode_png().map_err(|err| Error::Png {
+        msg: err.to_string(),
+    })
+}
+
+// accepts a Variant, a Sudoku or a list of 81 cells
+pub(crate) fn extract_variant(puzzle: &PyAny) -> PyResult<Variant> {
+    if let Ok(variant) = puzzle.extract::<Variant>() {
+        return Ok(variant);
+    }
+    let puzzle = match puzzle.extract::<Sudoku>() {
+        Ok(puzzle) => puzzle,
+        Err(_) => Sudoku::from_slice(&puzzle.extract::<Vec<usize>>()?)?,
+    };
+
+    Ok(Variant::new(puzzle))
+}
+
+fn extract_filled(filled: Option<&PyAny>) -> PyResult<Sudoku> {
+    match filled {
+        None => Ok(Sudoku::default()),
+        Some(filled) => match filled.extract::<Sudoku>() {
+            Ok(filled) => Ok(filled),
+            Err(_) => Ok(Sudoku::from_slice(&filled.extract::<Vec<usize>>()?)?),
+        },
+    }
+}
+
+fn py_options(
+    cell_size: f64,
+    font_family: &str,
+    font_file: Option<PathBuf>,
+    highlights: Option<Vec<(usize, usize)>>,
+    pencil_marks: bool,
+) -> Options {
+    let highlights = highlights
+        .unwrap_or_default()
+        .iter()
+        .filter(|(r, c)| *r < 9 && *c < 9)
+        .map(|(r, c)| r * 9 + c)
+        .collect();
+
+    Options {
+        cell_size,
+        font_family: font_family.to_owned(),
+        font_file,
+        highlights,
+        pencil_marks,
+        ..Options::default()
+    }
+}
+
+/// Draws a `Sudoku`, `Variant` or list of 81 cells as SVG. `filled` holds
+/// digits to draw in a lighter colour, such as a solution, and `highlights`
+/// lists `(row, col)` cells to shade.
+#[pyfunction(
+    filled = "None",
+    pencil_marks = "false",
+    highlights = "None",
+    cell_size = "48.0",
+    font_family = "\"sans-serif\""
+)]
+pub fn render_svg(
+    puzzle: &PyAny,
+    filled: Option<&PyAny>,
+    pencil_marks: bool,
+    highlights: Option<Vec<(usize, usize)>>,
+    cell_size: f64,
+    font_family: &str,
+) -> PyResult<String> {
+    let options = py_options(cell_size, font_family, None, highlights, pencil_marks);
+
+    Ok(svg(
+        &extract_variant(puzzle)?,
+        &extract_filled(filled)?,
+        &options,
+    ))
+}
+
+/// Like `render_svg`, but returns PNG bytes. `font_file` loads an extra font
+/// for `font_family` to refer to.
+#[pyfunction(
+    filled = "None",
+    pencil_marks = "false",
+    highlights = "None",
+    cell_size = "48.0",
+    font_family = "\"sans-serif\"",
+    font_file = "None"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn render_png<'py>(
+    py: Python<'py>,
+    puzzle: &PyAny,
+    filled: Option<&PyAny>,
+    pencil_marks: bool,
+    highlights: Option<Vec<(usize, usize)>>,
+    cell_size: f64,
+    font_family: &str,
+    font_file: Option<PathBuf>,
+) -> PyResult<&'py PyBytes> {
+    let options = py_options(cell_size, font_family, font_file, highlights, pencil_marks);
+    let (variant, filled) = (extract_variant(puzzle)?, extract_filled(filled)?);
+
+    let bytes = py.allow_threads(|| png(&variant, &filled, &options))?;
+
+    Ok(PyBytes::new(py, &bytes))
+}
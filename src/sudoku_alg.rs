@@ -12,8 +12,21 @@
 // either express or implied. See the License for the specific
 // language governing permissions and limitations under the License.
 
-use crate::dancing_links::{DancingLinks, Error as DlxError};
-use pyo3::{exceptions::PyTypeError, prelude::*};
+use crate::arrays;
+use crate::dancing_links::{
+    DancingLinks, Error as DlxError, Events, MinimumRemainingValues, SearchStats,
+};
+use crate::exact_cover::EventIter;
+use crate::puzzle::Sudoku;
+use crate::text::{self, Style};
+use pyo3::{
+    exceptions::PyTypeError,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 // Row-Column | Row-Number | Column-Number | Box-Number
 const WIDTH: usize = 9 * 9 * 4;
@@ -27,11 +40,19 @@ lazy_static! {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
 pub enum Error {
     InvalidGrid { got: usize },
     InvalidCell { got: usize },
+    InvalidPosition { row: usize, col: usize },
+    InvalidRegion { got: usize },
+    InvalidBand { got: usize },
+    InvalidPermutation { got: Vec<usize> },
+    InvalidSymmetry { got: String },
     DancingLinks { inner: DlxError },
     MultipleSolutions { found: usize },
+    Unsolved,
 }
 
 impl std::fmt::Display for Error {
@@ -43,12 +64,30 @@ impl std::fmt::Display for Error {
             Error::InvalidCell { got } => {
                 write!(f, "invalid cell: got {got}!")
             }
+            Error::InvalidPosition { row, col } => {
+                write!(f, "invalid position: ({row}, {col}) is outside the grid!")
+            }
+            Error::InvalidRegion { got } => {
+                write!(f, "invalid region: got {got}, expected 0 to 8!")
+            }
+            Error::InvalidBand { got } => {
+                write!(f, "invalid band or stack: got {got}, expected 0 to 2!")
+            }
+            Error::InvalidPermutation { got } => {
+                write!(f, "invalid permutation: got {got:?}!")
+            }
+            Error::InvalidSymmetry { got } => {
+                write!(f, "invalid symmetry: got {got:?}!")
+            }
             Error::MultipleSolutions { found } => {
                 write!(f, "multiple solutions found: {found} solutions!")
             }
             Error::DancingLinks { inner } => {
                 write!(f, "dancing links error: {inner}")
             }
+            Error::Unsolved => {
+                write!(f, "puzzle was not solved: solver thread panicked!")
+            }
         }
     }
 }
@@ -74,6 +113,22 @@ impl From<Error> for PyErr {
     }
 }
 
+impl IntoPy<PyObject> for SearchStats {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let dict = PyDict::new(py);
+        let branching: Vec<f64> = self.levels.iter().map(|l| l.branching_factor()).collect();
+
+        // keys are fixed strings, so setting them can't fail
+        dict.set_item("nodes", self.nodes).unwrap();
+        dict.set_item("updates", self.updates).unwrap();
+        dict.set_item("max_depth", self.max_depth).unwrap();
+        dict.set_item("solutions", self.solutions).unwrap();
+        dict.set_item("branching", branching).unwrap();
+
+        dict.into()
+    }
+}
+
 fn create_matrix() -> Vec<bool> {
     let mut matrix = Vec::with_capacity(HEIGHT * WIDTH);
 
@@ -100,15 +155,19 @@ fn create_matrix() -> Vec<bool> {
     matrix
 }
 
-fn decode_solution(solution: &[usize]) -> [usize; 81] {
+/// The `(row, col, digit)` placed by a row of the exact cover matrix used for
+/// sudoku, as reported by [`search_events`].
+pub fn decode_row(row: usize) -> (usize, usize, usize) {
+    (row / 81, (row / 9) % 9, row % 9 + 1)
+}
+
+pub(crate) fn decode_solution(solution: &[usize]) -> [usize; 81] {
     let mut puzzle = [0; 81];
 
     for i in solution {
-        let r = i / 81;
-        let c = (i / 9) % 9;
-        let n = i % 9;
+        let (r, c, n) = decode_row(*i);
 
-        puzzle[r * 9 + c] = n + 1;
+        puzzle[r * 9 + c] = n;
     }
 
     puzzle
@@ -134,49 +193,644 @@ fn encode_puzzle(puzzle: &[usize]) -> Result<Vec<usize>, Error> {
     Ok(partial_solution)
 }
 
-#[pyfunction]
+/// Solves a puzzle with a unique solution. The search stops at the second
+/// solution, so puzzles with many fail as quickly as those with two.
 pub fn solve(puzzle: Vec<usize>) -> Result<[usize; 81], Error> {
+    let (dlx, partial_solution) = prepare(&puzzle)?;
+
+    let solutions = dlx
+        .into_solutions(Some(&partial_solution[..]))?
+        .take(2)
+        .collect::<Result<_, _>>()?;
+
+    unique_solution(solutions)
+}
+
+/// Accepts a list, a NumPy array or a `Sudoku`, and returns the solution in
+/// the same form.
+#[pyfunction]
+#[pyo3(name = "solve")]
+pub fn py_solve(py: Python<'_>, puzzle: &PyAny) -> PyResult<PyObject> {
+    arrays::map_puzzle(py, puzzle, solve)
+}
+
+/// Solves every puzzle on `threads` workers (all available cores by default),
+/// returning one result per puzzle in the same order.
+pub fn solve_many(
+    puzzles: Vec<Vec<usize>>,
+    threads: Option<usize>,
+) -> Vec<Result<[usize; 81], Error>> {
+    let threads = threads
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .clamp(1, puzzles.len().max(1));
+
+    // workers pull puzzles in order until none are left
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<_> = puzzles.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut finished = Vec::new();
+
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match puzzles.get(i) {
+                            Some(puzzle) => finished.push((i, solve(puzzle.clone()))),
+                            None => break,
+                        }
+                    }
+
+                    finished
+                })
+            })
+            .collect();
+
+        // a panicking worker leaves its puzzles unsolved rather than failing the batch
+        for handle in handles {
+            if let Ok(finished) = handle.join() {
+                for (i, result) in finished {
+                    results[i] = Some(result);
+                }
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or(Err(Error::Unsolved)))
+        .collect()
+}
+
+/// Python version of [`solve_many`]: failed puzzles are returned as exception
+/// instances instead of raising, including items that aren't lists of digits.
+#[pyfunction(threads = "None")]
+#[pyo3(name = "solve_many")]
+pub fn py_solve_many(
+    py: Python<'_>,
+    puzzles: Vec<&PyAny>,
+    threads: Option<usize>,
+) -> Vec<PyObject> {
+    // convert each item on its own, so one bad item only fails its slot
+    let puzzles: Vec<PyResult<Vec<usize>>> = puzzles.iter().map(|p| p.extract()).collect();
+    let valid: Vec<Vec<usize>> = puzzles.iter().flatten().cloned().collect();
+    let mut solved = py.allow_threads(|| solve_many(valid, threads)).into_iter();
+
+    puzzles
+        .into_iter()
+        .map(
+            |puzzle| match puzzle.map(|_| solved.next().expect("one result per puzzle")) {
+                Ok(Ok(solution)) => solution.into_py(py),
+                Ok(Err(err)) => PyErr::from(err).into_py(py),
+                Err(err) => err.into_py(py),
+            },
+        )
+        .collect()
+}
+
+/// The exact cover matrix of an empty sudoku: a row per cell and digit, and
+/// columns for cells, row digits, column digits and box digits. Puzzles are
+/// solved by placing their givens with `partial_solution`.
+pub fn matrix() -> Result<DancingLinks, Error> {
+    Ok(DancingLinks::new(&MATRIX[..], WIDTH, HEIGHT)?)
+}
+
+fn prepare(puzzle: &[usize]) -> Result<(DancingLinks, Vec<usize>), Error> {
     if puzzle.len() != 81 {
         return Err(Error::InvalidGrid { got: puzzle.len() });
     }
 
-    let dlx = DancingLinks::new(&MATRIX[..], WIDTH, HEIGHT)?;
+    let dlx = matrix()?;
 
-    let partial_solution = encode_puzzle(&puzzle)?;
+    let partial_solution = encode_puzzle(puzzle)?;
 
-    let solutions = dlx.solve(Some(&partial_solution[..]))?;
+    Ok((dlx, partial_solution))
+}
+
+/// Like [`solve`], also returning statistics of the search. Like `solve`,
+/// the search stops at the second solution.
+pub fn solve_with_stats(puzzle: Vec<usize>) -> Result<([usize; 81], SearchStats), Error> {
+    let (dlx, partial_solution) = prepare(&puzzle)?;
+
+    let (solutions, stats) = dlx.solve_with_stats(
+        Some(&partial_solution[..]),
+        Some(2),
+        &mut MinimumRemainingValues,
+    )?;
 
+    Ok((unique_solution(solutions)?, stats))
+}
+
+fn unique_solution(solutions: Vec<Vec<usize>>) -> Result<[usize; 81], Error> {
     match solutions.len() {
+        0 => Err(DlxError::NoSolutions.into()),
         1 => Ok(decode_solution(&solutions[0][..])),
         n => Err(Error::MultipleSolutions { found: n }),
     }
 }
 
 #[pyfunction]
-pub fn print_puzzle(puzzle: Vec<usize>) -> Result<(), Error> {
-    if puzzle.len() != 81 {
-        return Err(Error::InvalidGrid { got: puzzle.len() });
+#[pyo3(name = "solve_with_stats")]
+pub fn py_solve_with_stats(
+    py: Python<'_>,
+    puzzle: Vec<usize>,
+) -> Result<([usize; 81], SearchStats), Error> {
+    py.allow_threads(|| solve_with_stats(puzzle))
+}
+
+pub fn random_solution(partial: Vec<usize>, seed: u64) -> Result<[usize; 81], Error> {
+    let (dlx, partial_solution) = prepare(&partial)?;
+
+    let solutions = dlx.solve_random(Some(&partial_solution[..]), seed, Some(1))?;
+
+    Ok(decode_solution(&solutions[0][..]))
+}
+
+#[pyfunction]
+#[pyo3(name = "random_solution")]
+pub fn py_random_solution(py: Python<'_>, partial: &PyAny, seed: u64) -> PyResult<PyObject> {
+    arrays::map_puzzle(py, partial, |partial| random_solution(partial, seed))
+}
+
+/// Each step of the search for a puzzle's solutions, for showing the search
+/// as it happens. Rows can be turned into placements with [`decode_row`], and
+/// the givens are placed before the first event.
+pub fn search_events(puzzle: &[usize]) -> Result<Events, Error> {
+    let (dlx, partial_solution) = prepare(puzzle)?;
+
+    Ok(dlx.into_events(Some(&partial_solution[..]))?)
+}
+
+/// Python version of [`search_events`]. Events are dicts whose row events
+/// also have the `cell` as a `(row, col)` pair and the `digit`, and whose
+/// solutions also have the `grid`.
+#[pyfunction]
+#[pyo3(name = "search_events")]
+pub fn py_search_events(puzzle: Vec<usize>) -> PyResult<EventIter> {
+    Ok(EventIter::new(search_events(&puzzle)?, true))
+}
+
+/// Finds up to `limit` solutions, stopping as soon as they're found. Unlike
+/// [`solve`], having no solution or several isn't an error.
+pub fn solutions(puzzle: &[usize], limit: usize) -> Result<Vec<[usize; 81]>, Error> {
+    let (dlx, partial_solution) = prepare(puzzle)?;
+    let mut found = Vec::new();
+
+    for solution in dlx.into_solutions(Some(&partial_solution[..]))?.take(limit) {
+        found.push(decode_solution(&solution?));
+    }
+
+    Ok(found)
+}
+
+/// Generates a puzzle with a unique solution by emptying the cells of a
+/// random solution in a random order, keeping each clue whose removal would
+/// allow a second solution. No clue can be removed from the result.
+pub fn generate(seed: u64) -> Result<[usize; 81], Error> {
+    let mut puzzle = random_solution(vec![0; 81], seed)?;
+    let mut order: Vec<usize> = (0..81).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    for i in order {
+        let n = puzzle[i];
+        puzzle[i] = 0;
+        if solutions(&puzzle, 2)?.len() != 1 {
+            puzzle[i] = n;
+        }
     }
 
-    for row in 0..9 {
-        for col in 0..9 {
-            match puzzle[row * 9 + col] {
-                0 => print!(" "),
-                n => print!("{n}"),
+    Ok(puzzle)
+}
+
+pub fn count_solutions(
+    puzzle: &[usize],
+    split_depth: usize,
+    threads: Option<usize>,
+) -> Result<usize, Error> {
+    let (dlx, partial_solution) = prepare(puzzle)?;
+
+    Ok(dlx.count_parallel(Some(&partial_solution[..]), split_depth, threads)?)
+}
+
+#[pyfunction(split_depth = "2", threads = "None")]
+#[pyo3(name = "count_solutions")]
+pub fn py_count_solutions(
+    py: Python<'_>,
+    puzzle: Vec<usize>,
+    split_depth: usize,
+    threads: Option<usize>,
+) -> Result<usize, Error> {
+    py.allow_threads(|| count_solutions(&puzzle, split_depth, threads))
+}
+
+/// Rows, columns and regions, in the order [`verify_solution`] checks them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum UnitKind {
+    Row,
+    Column,
+    /// a 3x3 box, or an irregular region
+    Region,
+}
+
+impl std::fmt::Display for UnitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitKind::Row => write!(f, "row"),
+            UnitKind::Column => write!(f, "column"),
+            UnitKind::Region => write!(f, "region"),
+        }
+    }
+}
+
+/// Something wrong with a solution. Cells are numbered row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Violation {
+    EmptyCell {
+        cell: usize,
+    },
+    ChangedGiven {
+        cell: usize,
+        given: usize,
+        got: usize,
+    },
+    /// `digit` appears in each of `cells`, which share a unit
+    Repeated {
+        unit: UnitKind,
+        index: usize,
+        digit: usize,
+        cells: Vec<usize>,
+    },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::EmptyCell { cell } => {
+                write!(f, "cell ({}, {}) is empty", cell / 9, cell % 9)
+            }
+            Violation::ChangedGiven { cell, given, got } => write!(
+                f,
+                "cell ({}, {}) has {got} instead of the given {given}",
+                cell / 9,
+                cell % 9
+            ),
+            Violation::Repeated {
+                unit,
+                index,
+                digit,
+                cells,
+            } => write!(f, "{digit} appears {} times in {unit} {index}", cells.len()),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Violation {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let dict = PyDict::new(py);
+        let position = |cell: usize| (cell / 9, cell % 9);
+
+        // keys are fixed strings, so setting them can't fail
+        match self {
+            Violation::EmptyCell { cell } => {
+                dict.set_item("kind", "empty_cell").unwrap();
+                dict.set_item("cell", position(cell)).unwrap();
+            }
+            Violation::ChangedGiven { cell, given, got } => {
+                dict.set_item("kind", "changed_given").unwrap();
+                dict.set_item("cell", position(cell)).unwrap();
+                dict.set_item("given", given).unwrap();
+                dict.set_item("got", got).unwrap();
+            }
+            Violation::Repeated {
+                unit,
+                index,
+                digit,
+                cells,
+            } => {
+                let cells: Vec<_> = cells.into_iter().map(position).collect();
+                dict.set_item("kind", "repeated").unwrap();
+                dict.set_item("unit", unit.to_string()).unwrap();
+                dict.set_item("index", index).unwrap();
+                dict.set_item("digit", digit).unwrap();
+                dict.set_item("cells", cells).unwrap();
             }
-            if col != 8 {
-                print!(" ");
-                if col % 3 == 2 {
-                    print!("| ");
+        }
+
+        dict.into()
+    }
+}
+
+fn check_cells(cells: &[usize]) -> Result<(), Error> {
+    if cells.len() != 81 {
+        return Err(Error::InvalidGrid { got: cells.len() });
+    }
+    match cells.iter().find(|n| **n > 9) {
+        Some(n) => Err(Error::InvalidCell { got: *n }),
+        None => Ok(()),
+    }
+}
+
+/// Checks a solution against its givens by looking at every cell, row,
+/// column and 3x3 box, without searching. An empty list means the solution
+/// is correct.
+pub fn verify_solution(givens: &[usize], solution: &[usize]) -> Result<Vec<Violation>, Error> {
+    let mut boxes = [0; 81];
+    for (i, b) in boxes.iter_mut().enumerate() {
+        *b = (i / 27) * 3 + (i % 9) / 3;
+    }
+
+    verify_solution_in_regions(givens, solution, &boxes)
+}
+
+/// Like [`verify_solution`], but with irregular regions in place of the
+/// boxes: `regions` gives the region of each cell, from 0 to 8.
+pub fn verify_solution_in_regions(
+    givens: &[usize],
+    solution: &[usize],
+    regions: &[usize],
+) -> Result<Vec<Violation>, Error> {
+    check_cells(givens)?;
+    check_cells(solution)?;
+    if regions.len() != 81 {
+        return Err(Error::InvalidGrid { got: regions.len() });
+    }
+    if let Some(region) = regions.iter().find(|r| **r > 8) {
+        return Err(Error::InvalidRegion { got: *region });
+    }
+
+    let mut violations = Vec::new();
+    for cell in 0..81 {
+        let (given, got) = (givens[cell], solution[cell]);
+        if got == 0 {
+            violations.push(Violation::EmptyCell { cell });
+        } else if given != 0 && given != got {
+            violations.push(Violation::ChangedGiven { cell, given, got });
+        }
+    }
+
+    let units = [UnitKind::Row, UnitKind::Column, UnitKind::Region];
+    for unit in units {
+        for index in 0..9 {
+            let in_unit = |cell: &usize| match unit {
+                UnitKind::Row => cell / 9 == index,
+                UnitKind::Column => cell % 9 == index,
+                UnitKind::Region => regions[*cell] == index,
+            };
+            for digit in 1..=9 {
+                let cells: Vec<usize> = (0..81)
+                    .filter(in_unit)
+                    .filter(|cell| solution[*cell] == digit)
+                    .collect();
+                if cells.len() > 1 {
+                    violations.push(Violation::Repeated {
+                        unit,
+                        index,
+                        digit,
+                        cells,
+                    });
                 }
-            } else {
-                println!();
             }
         }
-        if row % 3 == 2 && row != 8 {
-            println!("------+-------+------");
+    }
+
+    Ok(violations)
+}
+
+// a `Sudoku`, a NumPy array, or a flat or 9x9 nested list
+fn extract_cells(obj: &PyAny) -> PyResult<Vec<usize>> {
+    if arrays::is_array(obj) {
+        return Ok(arrays::read_puzzle(obj)?.cells);
+    }
+    if let Ok(puzzle) = obj.extract::<Sudoku>() {
+        return Ok(puzzle.cells().to_vec());
+    }
+    if let Ok(rows) = obj.extract::<Vec<Vec<usize>>>() {
+        return Ok(rows.concat());
+    }
+
+    obj.extract()
+}
+
+/// Python version of [`verify_solution`], taking puzzles, lists or NumPy
+/// arrays, and optional irregular regions. Violations are returned as dicts with cells
+/// as `(row, col)` pairs.
+#[pyfunction(regions = "None")]
+#[pyo3(name = "verify_solution")]
+pub fn py_verify_solution(
+    py: Python<'_>,
+    givens: &PyAny,
+    solution: &PyAny,
+    regions: Option<&PyAny>,
+) -> PyResult<Py<PyList>> {
+    let givens = extract_cells(givens)?;
+    let solution = extract_cells(solution)?;
+    let violations = match regions {
+        Some(regions) => verify_solution_in_regions(&givens, &solution, &extract_cells(regions)?)?,
+        None => verify_solution(&givens, &solution)?,
+    };
+
+    Ok(PyList::new(py, violations.into_iter().map(|v| v.into_py(py))).into())
+}
+
+/// Symmetries of the clues kept by [`minimize`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Symmetry {
+    None,
+    /// the same after rotating by 180 degrees
+    HalfTurn,
+    /// the same after rotating by 90 degrees
+    QuarterTurn,
+    /// the same when mirrored left to right
+    LeftRight,
+    /// the same when mirrored top to bottom
+    TopBottom,
+    /// the same when mirrored on the diagonal from top left to bottom right
+    Diagonal,
+    /// the same when mirrored on the diagonal from bottom left to top right
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    // cells that must be clues if `cell` is, including `cell`
+    fn orbit(&self, cell: usize) -> Vec<usize> {
+        let (r, c) = (cell / 9, cell % 9);
+        let mut orbit = vec![cell];
+
+        match self {
+            Symmetry::None => {}
+            Symmetry::HalfTurn => orbit.push(80 - cell),
+            Symmetry::QuarterTurn => orbit.extend([c * 9 + 8 - r, 80 - cell, (8 - c) * 9 + r]),
+            Symmetry::LeftRight => orbit.push(r * 9 + 8 - c),
+            Symmetry::TopBottom => orbit.push((8 - r) * 9 + c),
+            Symmetry::Diagonal => orbit.push(c * 9 + r),
+            Symmetry::AntiDiagonal => orbit.push((8 - c) * 9 + 8 - r),
         }
+
+        orbit
+    }
+}
+
+impl std::str::FromStr for Symmetry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Symmetry::None),
+            "half_turn" => Ok(Symmetry::HalfTurn),
+            "quarter_turn" => Ok(Symmetry::QuarterTurn),
+            "left_right" => Ok(Symmetry::LeftRight),
+            "top_bottom" => Ok(Symmetry::TopBottom),
+            "diagonal" => Ok(Symmetry::Diagonal),
+            "anti_diagonal" => Ok(Symmetry::AntiDiagonal),
+            _ => Err(Error::InvalidSymmetry { got: s.to_owned() }),
+        }
+    }
+}
+
+// counts the solutions of a puzzle, stopping at the second. `dlx` is the
+// empty matrix, cloned for each puzzle instead of being rebuilt
+fn count_up_to_two(dlx: &DancingLinks, puzzle: &[usize]) -> Result<usize, Error> {
+    if puzzle.len() != 81 {
+        return Err(Error::InvalidGrid { got: puzzle.len() });
     }
+    let partial_solution = encode_puzzle(puzzle)?;
+
+    let mut found = 0;
+    for solution in dlx
+        .clone()
+        .into_solutions(Some(&partial_solution[..]))?
+        .take(2)
+    {
+        solution?;
+        found += 1;
+    }
+
+    Ok(found)
+}
+
+// returns the empty matrix for further checks
+fn check_unique(puzzle: &[usize]) -> Result<DancingLinks, Error> {
+    let dlx = matrix()?;
+
+    match count_up_to_two(&dlx, puzzle)? {
+        0 => Err(DlxError::NoSolutions.into()),
+        1 => Ok(dlx),
+        found => Err(Error::MultipleSolutions { found }),
+    }
+}
+
+// the clues of a unique puzzle that can each be removed, found one at a time
+fn redundant<'a>(
+    dlx: &'a DancingLinks,
+    puzzle: &'a [usize],
+) -> impl Iterator<Item = Result<usize, Error>> + 'a {
+    (0..81).filter(|i| puzzle[*i] != 0).filter_map(|cell| {
+        let mut removed = puzzle.to_vec();
+        removed[cell] = 0;
+        match count_up_to_two(dlx, &removed) {
+            Ok(1) => Some(Ok(cell)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        }
+    })
+}
+
+/// Clues of a puzzle with a unique solution that could each be removed on
+/// their own without allowing a second solution. Removing several of them
+/// at once might.
+pub fn redundant_clues(puzzle: &[usize]) -> Result<Vec<usize>, Error> {
+    let dlx = check_unique(puzzle)?;
+
+    redundant(&dlx, puzzle).collect()
+}
+
+/// Returns true if a puzzle has a unique solution and every clue is needed
+/// to keep it unique.
+pub fn is_minimal(puzzle: &[usize]) -> Result<bool, Error> {
+    let dlx = check_unique(puzzle)?;
+
+    let first = redundant(&dlx, puzzle).next().transpose()?;
+
+    Ok(first.is_none())
+}
+
+/// Removes clues from a puzzle with a unique solution while it stays
+/// unique, trying cells row by row. Clues are removed together with the
+/// cells `symmetry` maps them to, so a symmetric puzzle stays symmetric,
+/// and no such group can be removed from the result. With
+/// [`Symmetry::None`] the result is minimal.
+pub fn minimize(puzzle: &[usize], symmetry: Symmetry) -> Result<[usize; 81], Error> {
+    let dlx = check_unique(puzzle)?;
+    let mut minimized = [0; 81];
+    minimized.copy_from_slice(puzzle);
+
+    for cell in 0..81 {
+        let orbit = symmetry.orbit(cell);
+        // each group is tried once, from its first cell
+        if orbit.iter().any(|i| *i < cell) || orbit.iter().all(|i| minimized[*i] == 0) {
+            continue;
+        }
+
+        let mut removed = minimized;
+        for i in orbit {
+            removed[i] = 0;
+        }
+        if count_up_to_two(&dlx, &removed)? == 1 {
+            minimized = removed;
+        }
+    }
+
+    Ok(minimized)
+}
+
+/// Python version of [`redundant_clues`], with cells as `(row, col)` pairs.
+#[pyfunction]
+#[pyo3(name = "redundant_clues")]
+pub fn py_redundant_clues(py: Python<'_>, puzzle: Vec<usize>) -> PyResult<Vec<(usize, usize)>> {
+    let cells = py.allow_threads(|| redundant_clues(&puzzle))?;
+
+    Ok(cells.into_iter().map(|i| (i / 9, i % 9)).collect())
+}
+
+#[pyfunction]
+#[pyo3(name = "is_minimal")]
+pub fn py_is_minimal(py: Python<'_>, puzzle: Vec<usize>) -> PyResult<bool> {
+    Ok(py.allow_threads(|| is_minimal(&puzzle))?)
+}
+
+/// Python version of [`minimize`], with `symmetry` given by name, such as
+/// `"half_turn"`.
+#[pyfunction(symmetry = "\"none\"")]
+#[pyo3(name = "minimize")]
+pub fn py_minimize(py: Python<'_>, puzzle: &PyAny, symmetry: &str) -> PyResult<PyObject> {
+    let symmetry: Symmetry = symmetry.parse()?;
+
+    arrays::map_puzzle(py, puzzle, |puzzle| minimize(&puzzle, symmetry))
+}
+
+/// Formats a puzzle as 9 rows with box separators, see [`text::format_cells`]
+/// for other styles.
+pub fn format_puzzle(puzzle: &[usize]) -> Result<String, Error> {
+    if puzzle.len() != 81 {
+        return Err(Error::InvalidGrid { got: puzzle.len() });
+    }
+
+    text::format_cells(puzzle, None, Style::Grid).map_err(|err| match err {
+        text::Error::InvalidCell { got, .. } => Error::InvalidCell { got },
+        _ => unreachable!("grid has 81 cells"),
+    })
+}
+
+#[pyfunction]
+pub fn print_puzzle(puzzle: Vec<usize>) -> Result<(), Error> {
+    print!("{}", format_puzzle(&puzzle)?);
 
     Ok(())
 }
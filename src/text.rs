@@ -0,0 +1,442 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use crate::puzzle::Sudoku;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Error {
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        got: char,
+    },
+    InvalidCellCount {
+        got: usize,
+    },
+    InvalidRowCount {
+        got: usize,
+    },
+    InvalidStyle {
+        got: String,
+    },
+    InvalidGridSize {
+        got: usize,
+    },
+    InvalidCell {
+        got: usize,
+        size: usize,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidCharacter { line, column, got } => {
+                write!(
+                    f,
+                    "invalid character {got:?} at line {line}, column {column}!"
+                )
+            }
+            Error::InvalidCellCount { got } => {
+                write!(f, "invalid number of cells: got {got}, expected 81!")
+            }
+            Error::InvalidRowCount { got } => {
+                write!(f, "invalid number of grid rows: got {got}, expected 9!")
+            }
+            Error::InvalidStyle { got } => {
+                write!(f, "invalid style: got {got:?}!")
+            }
+            Error::InvalidGridSize { got } => {
+                write!(
+                    f,
+                    "invalid grid: {got} cells don't make a sudoku of up to 25x25!"
+                )
+            }
+            Error::InvalidCell { got, size } => {
+                write!(f, "invalid cell: got {got}, expected 0 to {size}!")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        PyErr::new::<PyValueError, _>(err.to_string())
+    }
+}
+
+/// Text layouts understood by [`format`]. Only `Line`, `Dotted`, `Compact`
+/// and `Grid` can be read back by [`parse`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Style {
+    /// 81 digits on one line, with `0` for empty cells
+    Line,
+    /// 81 characters on one line, with `.` for empty cells
+    Dotted,
+    /// 9 rows with box separators, as written by `print_puzzle`
+    Grid,
+    /// 9 rows of 9 characters, with `.` for empty cells
+    Compact,
+    /// a grid drawn with Unicode box-drawing characters
+    Boxed,
+    /// the candidates of each empty cell in a 3x3 block, making a 27x27 grid
+    Pencil,
+    /// like `Grid`, with givens in bold and other digits in colour, using
+    /// ANSI escape codes
+    Ansi,
+}
+
+impl std::str::FromStr for Style {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Style::Line),
+            "dotted" => Ok(Style::Dotted),
+            "grid" => Ok(Style::Grid),
+            "compact" => Ok(Style::Compact),
+            "boxed" => Ok(Style::Boxed),
+            "pencil" => Ok(Style::Pencil),
+            "ansi" => Ok(Style::Ansi),
+            _ => Err(Error::InvalidStyle { got: s.to_owned() }),
+        }
+    }
+}
+
+fn cell_value(ch: char) -> Option<usize> {
+    match ch {
+        '.' => Some(0),
+        '0'..='9' => Some(ch as usize - '0' as usize),
+        _ => None,
+    }
+}
+
+fn is_separator(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '|' | '-' | '+')
+}
+
+/// Parses a puzzle from an 81 character line, or from a text grid such as the
+/// one written by `print_puzzle`. Empty cells are written as `0` or `.`, and
+/// whitespace, `|`, `-` and `+` separators are ignored. In grids, a space
+/// where a cell is expected also counts as an empty cell.
+pub fn parse(text: &str) -> Result<Sudoku, Error> {
+    let mut cells = Vec::with_capacity(81);
+
+    for (line, row) in text.lines().enumerate() {
+        for (column, ch) in row.chars().enumerate() {
+            if is_separator(ch) {
+                continue;
+            }
+            match cell_value(ch) {
+                Some(n) => cells.push(n),
+                None => {
+                    return Err(Error::InvalidCharacter {
+                        line: line + 1,
+                        column: column + 1,
+                        got: ch,
+                    })
+                }
+            }
+        }
+    }
+
+    // fall back to reading cells by position when blanks are spaces
+    if cells.len() != 81 {
+        if text.lines().filter(|row| !row.trim().is_empty()).count() <= 1 {
+            return Err(Error::InvalidCellCount { got: cells.len() });
+        }
+        cells = parse_grid(text)?;
+    }
+
+    Ok(Sudoku::from_slice(&cells).expect("cells are digits and there are 81 of them"))
+}
+
+fn parse_grid(text: &str) -> Result<Vec<usize>, Error> {
+    let mut cells = Vec::with_capacity(81);
+    let mut rows = 0;
+
+    for (line, row) in text.lines().enumerate() {
+        // skip blank lines and rules between bands
+        if row.trim().is_empty() || row.contains(['-', '+']) {
+            continue;
+        }
+        rows += 1;
+
+        // cells sit two columns apart, with an extra "| " after each box
+        let chars: Vec<char> = row.chars().collect();
+        for i in 0..9 {
+            let column = 2 * i + 2 * (i / 3);
+            let ch = chars.get(column).copied().unwrap_or(' ');
+
+            match cell_value(ch) {
+                Some(n) => cells.push(n),
+                None if ch == ' ' => cells.push(0),
+                None => {
+                    return Err(Error::InvalidCharacter {
+                        line: line + 1,
+                        column: column + 1,
+                        got: ch,
+                    })
+                }
+            }
+        }
+    }
+
+    if rows != 9 {
+        return Err(Error::InvalidRowCount { got: rows });
+    }
+
+    Ok(cells)
+}
+
+/// Formats a puzzle. With [`Style::Ansi`], every digit is drawn as a given.
+pub fn format(puzzle: &Sudoku, style: Style) -> String {
+    format_cells(puzzle.cells(), None, style).expect("puzzle is a 9x9 sudoku")
+}
+
+/// Formats a grid of any size with square boxes, such as 4x4, 9x9 or 16x16,
+/// given row by row. Digits above 9 are written as letters from `A`.
+/// [`Style::Ansi`] draws cells that are set in `givens` as givens, or every
+/// digit if there are none.
+pub fn format_cells(
+    cells: &[usize],
+    givens: Option<&[usize]>,
+    style: Style,
+) -> Result<String, Error> {
+    let (size, box_size) = grid_size(cells.len())?;
+    if let Some(&got) = cells.iter().find(|n| **n > size) {
+        return Err(Error::InvalidCell { got, size });
+    }
+    if let Some(givens) = givens {
+        if givens.len() != cells.len() {
+            return Err(Error::InvalidCellCount { got: givens.len() });
+        }
+    }
+    let rows: Vec<&[usize]> = cells.chunks(size).collect();
+
+    let out = match style {
+        Style::Line => cells.iter().map(|n| digit(*n, '0')).collect(),
+        Style::Dotted => cells.iter().map(|n| digit(*n, '.')).collect(),
+        Style::Compact => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|n| digit(*n, '.'))
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect(),
+        Style::Grid => grid(&rows, box_size, |_, n| digit(n, ' ').to_string()),
+        Style::Ansi => grid(&rows, box_size, |i, n| {
+            match (n, givens.is_none_or(|givens| givens[i] != 0)) {
+                (0, _) => " ".to_owned(),
+                (n, true) => format!("\x1b[1m{}\x1b[0m", digit(n, ' ')),
+                (n, false) => format!("\x1b[34m{}\x1b[0m", digit(n, ' ')),
+            }
+        }),
+        Style::Boxed => boxed(&rows, box_size),
+        Style::Pencil => pencil(cells, size, box_size),
+    };
+
+    Ok(out)
+}
+
+// the side of a grid with `len` cells, and of its boxes
+fn grid_size(len: usize) -> Result<(usize, usize), Error> {
+    (1..=5)
+        .map(|box_size| (box_size * box_size, box_size))
+        .find(|(size, _)| size * size == len)
+        .ok_or(Error::InvalidGridSize { got: len })
+}
+
+fn digit(n: usize, blank: char) -> char {
+    match n {
+        0 => blank,
+        1..=9 => char::from(b'0' + n as u8),
+        n => char::from(b'A' + (n - 10) as u8),
+    }
+}
+
+// the line between bands, with `+` under each `|` of `row`
+fn rule(row: &str) -> String {
+    let mut rule: String = strip_ansi(row.trim_end_matches('\n'))
+        .chars()
+        .map(|ch| if ch == '|' { '+' } else { '-' })
+        .collect();
+    rule.push('\n');
+    rule
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::new();
+    let mut escape = false;
+
+    for ch in text.chars() {
+        match ch {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if !escape => out.push(ch),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+// rows with cells a space apart, and `|` and `-` between boxes
+fn grid(rows: &[&[usize]], box_size: usize, cell: impl Fn(usize, usize) -> String) -> String {
+    let size = rows.len();
+    let mut out = String::new();
+
+    for (row, cells) in rows.iter().enumerate() {
+        let start = out.len();
+        for (col, n) in cells.iter().enumerate() {
+            out += &cell(row * size + col, *n);
+            if col != size - 1 {
+                out.push(' ');
+                if col % box_size == box_size - 1 {
+                    out.push_str("| ");
+                }
+            } else {
+                out.push('\n');
+            }
+        }
+        if row % box_size == box_size - 1 && row != size - 1 {
+            out += &rule(&out[start..]);
+        }
+    }
+
+    out
+}
+
+// heavy lines around boxes and light lines between cells
+fn boxed(rows: &[&[usize]], box_size: usize) -> String {
+    let size = rows.len();
+    // left end, cell line, light joint, heavy joint, right end
+    let rule = |chars: [char; 5]| {
+        let mut line = String::from(chars[0]);
+        for col in 0..size {
+            line.extend([chars[1]; 3]);
+            line.push(match col {
+                _ if col == size - 1 => chars[4],
+                _ if col % box_size == box_size - 1 => chars[3],
+                _ => chars[2],
+            });
+        }
+        line.push('\n');
+        line
+    };
+    let mut out = rule(['┏', '━', '┯', '┳', '┓']);
+
+    for (row, cells) in rows.iter().enumerate() {
+        out.push('┃');
+        for (col, n) in cells.iter().enumerate() {
+            out.extend([' ', digit(*n, ' '), ' ']);
+            out.push(match col % box_size == box_size - 1 {
+                true => '┃',
+                false => '│',
+            });
+        }
+        out.push('\n');
+
+        out += &match row {
+            _ if row == size - 1 => rule(['┗', '━', '┷', '┻', '┛']),
+            _ if row % box_size == box_size - 1 => rule(['┣', '━', '┿', '╋', '┫']),
+            _ => rule(['┠', '─', '┼', '╂', '┨']),
+        };
+    }
+
+    out
+}
+
+// each cell is a block of box_size x box_size characters, with its candidates
+// and `.` for the other digits, or its digit in the middle
+fn pencil(cells: &[usize], size: usize, box_size: usize) -> String {
+    let seen: Vec<Vec<bool>> = (0..cells.len())
+        .map(|i| {
+            let (row, col) = (i / size, i % size);
+            let (top, left) = (row / box_size * box_size, col / box_size * box_size);
+            let mut seen = vec![false; size + 1];
+            for j in 0..size {
+                seen[cells[row * size + j]] = true;
+                seen[cells[j * size + col]] = true;
+                seen[cells[(top + j / box_size) * size + left + j % box_size]] = true;
+            }
+            seen
+        })
+        .collect();
+    let mut out = String::new();
+
+    for line in 0..size * box_size {
+        let start = out.len();
+        let (row, sub_row) = (line / box_size, line % box_size);
+        for col in 0..size {
+            let i = row * size + col;
+            for sub_col in 0..box_size {
+                let n = sub_row * box_size + sub_col + 1;
+                out.push(match cells[i] {
+                    0 if seen[i][n] => '.',
+                    0 => digit(n, '.'),
+                    n if sub_row == box_size / 2 && sub_col == box_size / 2 => digit(n, ' '),
+                    _ => ' ',
+                });
+            }
+            if col == size - 1 {
+                out.push('\n');
+            } else if col % box_size == box_size - 1 {
+                out.push_str(" | ");
+            } else {
+                out.push(' ');
+            }
+        }
+        if line % (box_size * box_size) == box_size * box_size - 1 && row != size - 1 {
+            out += &rule(&out[start..]);
+        }
+    }
+
+    out
+}
+
+/// Parses a puzzle from text, see [`parse`].
+#[pyfunction]
+#[pyo3(name = "parse")]
+pub fn py_parse(text: &str) -> Result<Sudoku, Error> {
+    parse(text)
+}
+
+fn extract_cells(puzzle: &PyAny) -> PyResult<Vec<usize>> {
+    match puzzle.extract::<Sudoku>() {
+        Ok(puzzle) => Ok(puzzle.cells().to_vec()),
+        Err(_) => puzzle.extract(),
+    }
+}
+
+/// Formats a `Sudoku`, or a list of cells of a 4x4, 9x9, 16x16 or 25x25 grid,
+/// in one of the `Style`s. With `"ansi"`, cells set in `givens` are drawn as
+/// givens and the others in colour.
+#[pyfunction(style = "\"grid\"", givens = "None")]
+#[pyo3(name = "format")]
+pub fn py_format(puzzle: &PyAny, style: &str, givens: Option<&PyAny>) -> PyResult<String> {
+    let cells = extract_cells(puzzle)?;
+    let givens = givens.map(extract_cells).transpose()?;
+
+    Ok(format_cells(&cells, givens.as_deref(), style.parse()?)?)
+}
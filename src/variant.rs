@@ -0,0 +1,396 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use crate::dancing_links::DancingLinks;
+use crate::puzzle::Sudoku;
+use crate::sudoku_alg::Error;
+use pyo3::prelude::*;
+
+/// A killer cage: its digits don't repeat and, if given, add up to `sum`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cage {
+    pub cells: Vec<usize>,
+    pub sum: Option<usize>,
+}
+
+/// Digits along each line add up to the number in the bulb. A bulb of
+/// several cells is read as a multi-digit number, in the order given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arrow {
+    pub bulb: Vec<usize>,
+    pub lines: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DotKind {
+    /// the two digits differ by the value, 1 for a white kropki dot
+    Difference,
+    /// one digit is the value times the other, 2 for a black kropki dot
+    Ratio,
+}
+
+/// A dot between two adjacent cells.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dot {
+    pub cells: (usize, usize),
+    pub kind: DotKind,
+    pub value: usize,
+}
+
+impl Dot {
+    fn allows(&self, a: usize, b: usize) -> bool {
+        match self.kind {
+            DotKind::Difference => a.abs_diff(b) == self.value,
+            DotKind::Ratio => a * self.value == b || b * self.value == a,
+        }
+    }
+}
+
+/// A sudoku with extra constraints. Cells are numbered row by row from 0 to
+/// 80, and `regions` gives the region of each cell, the 3x3 boxes unless the
+/// puzzle is irregular.
+#[pyclass(module = "sudoku")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variant {
+    pub givens: Sudoku,
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::regions"))]
+    pub regions: [usize; 81],
+    pub cages: Vec<Cage>,
+    pub thermos: Vec<Vec<usize>>,
+    pub arrows: Vec<Arrow>,
+    pub dots: Vec<Dot>,
+    /// digits don't repeat on the diagonal from bottom left to top right
+    pub positive_diagonal: bool,
+    /// digits don't repeat on the diagonal from top left to bottom right
+    pub negative_diagonal: bool,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Self::new(Sudoku::default())
+    }
+}
+
+impl Variant {
+    /// A classic sudoku with the given digits and no extra constraints.
+    pub fn new(givens: Sudoku) -> Self {
+        let mut regions = [0; 81];
+        for (i, region) in regions.iter_mut().enumerate() {
+            *region = (i / 27) * 3 + (i % 9) / 3;
+        }
+
+        Self {
+            givens,
+            regions,
+            cages: Vec::new(),
+            thermos: Vec::new(),
+            arrows: Vec::new(),
+            dots: Vec::new(),
+            positive_diagonal: false,
+            negative_diagonal: false,
+        }
+    }
+
+    // exact cover rows for placing each digit in each cell, numbered like
+    // `sudoku_alg`: cell * 9 + digit - 1
+    fn rows(&self) -> (Vec<Vec<usize>>, usize, usize) {
+        let mut primary = 81 * 4;
+        let diagonals = [self.positive_diagonal, self.negative_diagonal];
+        let positive = primary;
+        if self.positive_diagonal {
+            primary += 9;
+        }
+        let negative = primary;
+        if self.negative_diagonal {
+            primary += 9;
+        }
+
+        let rows = (0..81 * 9)
+            .map(|row| {
+                let (i, n) = (row / 9, row % 9);
+                let (r, c) = (i / 9, i % 9);
+
+                let mut columns = vec![
+                    i,
+                    81 + r * 9 + n,
+                    81 * 2 + c * 9 + n,
+                    81 * 3 + self.regions[i] * 9 + n,
+                ];
+                if diagonals[0] && r + c == 8 {
+                    columns.push(positive + n);
+                }
+                if diagonals[1] && r == c {
+                    columns.push(negative + n);
+                }
+
+                // digits may not repeat in a cage, but needn't all appear
+                for (k, cage) in self.cages.iter().enumerate() {
+                    if cage.cells.contains(&i) {
+                        columns.push(primary + k * 9 + n);
+                    }
+                }
+
+                columns
+            })
+            .collect();
+
+        (rows, primary, self.cages.len() * 9)
+    }
+
+    // checks the constraints exact cover can't express around cell `i`
+    fn check(&self, grid: &[usize; 81], i: usize) -> bool {
+        let cages_hold = self
+            .cages
+            .iter()
+            .filter(|cage| cage.cells.contains(&i))
+            .all(|cage| check_cage(grid, cage));
+        let thermos_hold = self
+            .thermos
+            .iter()
+            .filter(|thermo| thermo.contains(&i))
+            .all(|thermo| check_thermo(grid, thermo));
+        let arrows_hold = self
+            .arrows
+            .iter()
+            .filter(|arrow| {
+                arrow.bulb.contains(&i) || arrow.lines.iter().any(|line| line.contains(&i))
+            })
+            .all(|arrow| check_arrow(grid, arrow));
+        let dots_hold = self
+            .dots
+            .iter()
+            .filter(|dot| dot.cells.0 == i || dot.cells.1 == i)
+            .all(|dot| {
+                let (a, b) = (grid[dot.cells.0], grid[dot.cells.1]);
+                a == 0 || b == 0 || dot.allows(a, b)
+            });
+
+        cages_hold && thermos_hold && arrows_hold && dots_hold
+    }
+
+    /// Finds up to `limit` solutions.
+    pub fn solutions(&self, limit: Option<usize>) -> Result<Vec<Sudoku>, Error> {
+        let (rows, primary, secondary) = self.rows();
+        let dlx = DancingLinks::from_rows(&rows, primary, secondary)?;
+
+        // the search only checks cells it fills in, so check the givens here
+        let givens = self.givens.cells();
+        if !(0..81).all(|i| self.check(givens, i)) {
+            return Ok(Vec::new());
+        }
+
+        let partial: Vec<usize> = (0..81)
+            .filter(|i| self.givens.cells()[*i] != 0)
+            .map(|i| i * 9 + self.givens.cells()[i] - 1)
+            .collect();
+
+        let mut filter = |rows: &[usize]| {
+            let mut grid = [0; 81];
+            for row in rows {
+                grid[row / 9] = row % 9 + 1;
+            }
+
+            // only the last cell placed can have broken something
+            rows.last().is_none_or(|row| self.check(&grid, row / 9))
+        };
+
+        let solutions = match dlx.solve_filtered(Some(&partial), limit, &mut filter) {
+            Ok(solutions) => solutions,
+            Err(crate::dancing_links::Error::NoSolutions) => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(solutions
+            .iter()
+            .map(|solution| {
+                let mut cells = [0; 81];
+                for row in solution {
+                    cells[row / 9] = row % 9 + 1;
+                }
+                Sudoku::new(cells).expect("digits are between 1 and 9")
+            })
+            .collect())
+    }
+
+    /// Solves a puzzle with exactly one solution.
+    pub fn solve(&self) -> Result<Sudoku, Error> {
+        let solutions = self.solutions(Some(2))?;
+
+        match solutions[..] {
+            [] => Err(crate::dancing_links::Error::NoSolutions.into()),
+            [solution] => Ok(solution),
+            _ => Err(Error::MultipleSolutions {
+                found: solutions.len(),
+            }),
+        }
+    }
+}
+
+fn check_cage(grid: &[usize; 81], cage: &Cage) -> bool {
+    let sum = match cage.sum {
+        Some(sum) => sum,
+        None => return true,
+    };
+
+    let mut used = [false; 10];
+    let mut total = 0;
+    for i in &cage.cells {
+        used[grid[*i]] = true;
+        total += grid[*i];
+    }
+
+    // the empty cells need distinct unused digits
+    let empty = cage.cells.iter().filter(|i| grid[**i] == 0).count();
+    let free: Vec<usize> = (1..=9).filter(|n| !used[*n]).collect();
+    if free.len() < empty {
+        return false;
+    }
+    let min: usize = free[..empty].iter().sum();
+    let max: usize = free[free.len() - empty..].iter().sum();
+
+    total + min <= sum && sum <= total + max
+}
+
+fn check_thermo(grid: &[usize; 81], thermo: &[usize]) -> bool {
+    let len = thermo.len();
+    let mut last: Option<(usize, usize)> = None;
+
+    for (k, i) in thermo.iter().enumerate() {
+        let n = grid[*i];
+        if n == 0 {
+            continue;
+        }
+        // leave room for the cells below and above on the thermometer
+        if n < k + 1 || n + (len - 1 - k) > 9 {
+            return false;
+        }
+        if let Some((j, m)) = last {
+            if n < m + (k - j) {
+                return false;
+            }
+        }
+        last = Some((k, n));
+    }
+
+    true
+}
+
+fn check_arrow(grid: &[usize; 81], arrow: &Arrow) -> bool {
+    let bulb = arrow
+        .bulb
+        .iter()
+        .try_fold(0, |total, i| (grid[*i] != 0).then(|| total * 10 + grid[*i]));
+
+    arrow.lines.iter().all(|line| {
+        let empty = line.iter().filter(|i| grid[**i] == 0).count();
+        let total: usize = line.iter().map(|i| grid[*i]).sum();
+
+        match bulb {
+            Some(bulb) if empty == 0 => total == bulb,
+            Some(bulb) => total + empty <= bulb,
+            None => true,
+        }
+    })
+}
+
+// (row, col) pairs, the way cells are given to Python
+type Position = (usize, usize);
+
+fn cell_position(i: usize) -> Position {
+    (i / 9, i % 9)
+}
+
+fn cell_positions(cells: &[usize]) -> Vec<Position> {
+    cells.iter().map(|i| cell_position(*i)).collect()
+}
+
+#[pymethods]
+impl Variant {
+    #[getter]
+    fn givens(&self) -> Sudoku {
+        self.givens
+    }
+
+    /// Region of each cell as a 9x9 grid.
+    #[getter]
+    fn regions(&self) -> Vec<Vec<usize>> {
+        self.regions.chunks(9).map(<[_]>::to_vec).collect()
+    }
+
+    /// `(cells, sum)` for each cage, with cells as `(row, col)` pairs.
+    #[getter]
+    fn cages(&self) -> Vec<(Vec<Position>, Option<usize>)> {
+        self.cages
+            .iter()
+            .map(|cage| (cell_positions(&cage.cells), cage.sum))
+            .collect()
+    }
+
+    /// Cells of each thermometer, starting from the bulb.
+    #[getter]
+    fn thermos(&self) -> Vec<Vec<Position>> {
+        self.thermos.iter().map(|t| cell_positions(t)).collect()
+    }
+
+    /// `(bulb, lines)` for each arrow.
+    #[getter]
+    fn arrows(&self) -> Vec<(Vec<Position>, Vec<Vec<Position>>)> {
+        self.arrows
+            .iter()
+            .map(|arrow| {
+                let lines = arrow.lines.iter().map(|l| cell_positions(l)).collect();
+                (cell_positions(&arrow.bulb), lines)
+            })
+            .collect()
+    }
+
+    /// `(a, b, kind, value)` for each dot, with kind `"difference"` or `"ratio"`.
+    #[getter]
+    fn dots(&self) -> Vec<(Position, Position, &'static str, usize)> {
+        self.dots
+            .iter()
+            .map(|dot| {
+                let kind = match dot.kind {
+                    DotKind::Difference => "difference",
+                    DotKind::Ratio => "ratio",
+                };
+                let (a, b) = dot.cells;
+                (cell_position(a), cell_position(b), kind, dot.value)
+            })
+            .collect()
+    }
+
+    /// `(positive, negative)` diagonal constraints.
+    #[getter]
+    fn diagonals(&self) -> (bool, bool) {
+        (self.positive_diagonal, self.negative_diagonal)
+    }
+
+    #[pyo3(name = "solve")]
+    fn py_solve(&self, py: Python<'_>) -> PyResult<Sudoku> {
+        Ok(py.allow_threads(|| self.solve())?)
+    }
+
+    #[pyo3(name = "solutions")]
+    #[args(limit = "None")]
+    fn py_solutions(&self, py: Python<'_>, limit: Option<usize>) -> PyResult<Vec<Sudoku>> {
+        Ok(py.allow_threads(|| self.solutions(limit))?)
+    }
+}
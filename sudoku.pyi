@@ -0,0 +1,267 @@
+# Copyright 2022 Nathan Rowan
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing,
+# software distributed under the License is distributed on an
+# "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+# either express or implied. See the License for the specific
+# language governing permissions and limitations under the License.
+
+from typing import Any, Iterator, List, Optional, Sequence, Tuple, Union, overload
+
+import numpy as np
+import numpy.typing as npt
+from typing_extensions import Literal, TypedDict
+
+# a puzzle array of shape (81,) or (9, 9) with any integer dtype
+_IntArray = npt.NDArray[np.integer[Any]]
+
+class _SearchStats(TypedDict):
+    nodes: int
+    updates: int
+    max_depth: int
+    solutions: int
+    branching: List[float]
+
+# cells are (row, col) pairs; only the keys for the violation's kind are set
+class _Violation(TypedDict, total=False):
+    kind: Literal["empty_cell", "changed_given", "repeated"]
+    cell: Tuple[int, int]
+    given: int
+    got: int
+    unit: Literal["row", "column", "region"]
+    index: int
+    digit: int
+    cells: List[Tuple[int, int]]
+
+class _Backdoor(TypedDict):
+    size: int
+    cells: List[Tuple[int, int]]
+    digits: List[int]
+
+# keys depend on the kind; events of sudoku searches also have the `cell`
+# and `digit` of rows and the `grid` of solutions
+class _Event(TypedDict, total=False):
+    kind: Literal[
+        "choose_column",
+        "try_row",
+        "cover_column",
+        "uncover_column",
+        "backtrack",
+        "solution",
+    ]
+    column: int
+    size: int
+    depth: int
+    row: int
+    rows: List[int]
+    cell: Tuple[int, int]
+    digit: int
+    grid: List[int]
+
+_Cells = Union[Sudoku, _IntArray, Sequence[int], Sequence[Sequence[int]]]
+
+_Symmetry = Literal[
+    "none",
+    "half_turn",
+    "quarter_turn",
+    "left_right",
+    "top_bottom",
+    "diagonal",
+    "anti_diagonal",
+]
+
+class ShapeError(ValueError): ...
+class DtypeError(TypeError): ...
+
+@overload
+def solve(puzzle: Sudoku) -> Sudoku: ...
+@overload
+def solve(puzzle: _IntArray) -> _IntArray: ...
+@overload
+def solve(puzzle: Sequence[int]) -> List[int]: ...
+def solve_many(
+    puzzles: Sequence[Sequence[int]], threads: Optional[int] = None
+) -> List[Union[List[int], Exception]]: ...
+def solve_with_stats(puzzle: Sequence[int]) -> Tuple[List[int], _SearchStats]: ...
+@overload
+def random_solution(partial: Sudoku, seed: int) -> Sudoku: ...
+@overload
+def random_solution(partial: _IntArray, seed: int) -> _IntArray: ...
+@overload
+def random_solution(partial: Sequence[int], seed: int) -> List[int]: ...
+def count_solutions(
+    puzzle: Sequence[int], split_depth: int = 2, threads: Optional[int] = None
+) -> int: ...
+def verify_solution(
+    givens: _Cells, solution: _Cells, regions: Optional[_Cells] = None
+) -> List[_Violation]: ...
+def redundant_clues(puzzle: Sequence[int]) -> List[Tuple[int, int]]: ...
+def is_minimal(puzzle: Sequence[int]) -> bool: ...
+@overload
+def minimize(puzzle: Sudoku, symmetry: _Symmetry = "none") -> Sudoku: ...
+@overload
+def minimize(puzzle: _IntArray, symmetry: _Symmetry = "none") -> _IntArray: ...
+@overload
+def minimize(puzzle: Sequence[int], symmetry: _Symmetry = "none") -> List[int]: ...
+def backdoor(puzzle: Sudoku, max_size: int = 3) -> Optional[_Backdoor]: ...
+def search_events(puzzle: Sequence[int]) -> EventIter: ...
+def are_equivalent(a: Sudoku, b: Sudoku) -> bool: ...
+def to_dimacs(puzzle: Sudoku) -> str: ...
+def parse_dimacs_model(text: str) -> Sudoku: ...
+def print_puzzle(puzzle: Sequence[int]) -> None: ...
+def parse(text: str) -> Sudoku: ...
+def format(
+    puzzle: Union[Sudoku, Sequence[int]],
+    style: Literal[
+        "line", "dotted", "grid", "compact", "boxed", "pencil", "ansi"
+    ] = "grid",
+    givens: Optional[Union[Sudoku, Sequence[int]]] = None,
+) -> str: ...
+def read_file(
+    path: str, format: Optional[Literal["sdk", "ss", "sdx"]] = None
+) -> PuzzleFile: ...
+def write_file(
+    file: PuzzleFile, path: str, format: Optional[Literal["sdk", "ss", "sdx"]] = None
+) -> None: ...
+
+def import_fpuzzles(payload: str, skip_unsupported: bool = False) -> Variant: ...
+def render_svg(
+    puzzle: Union[Sudoku, Variant, Sequence[int]],
+    filled: Optional[Union[Sudoku, Sequence[int]]] = None,
+    pencil_marks: bool = False,
+    highlights: Optional[Sequence[Tuple[int, int]]] = None,
+    cell_size: float = 48.0,
+    font_family: str = "sans-serif",
+) -> str: ...
+def render_png(
+    puzzle: Union[Sudoku, Variant, Sequence[int]],
+    filled: Optional[Union[Sudoku, Sequence[int]]] = None,
+    pencil_marks: bool = False,
+    highlights: Optional[Sequence[Tuple[int, int]]] = None,
+    cell_size: float = 48.0,
+    font_family: str = "sans-serif",
+    font_file: Optional[str] = None,
+) -> bytes: ...
+
+class Sudoku:
+    def __init__(
+        self, cells: Union[str, Sequence[int], Sequence[Sequence[int]], _IntArray]
+    ) -> None: ...
+    def __getitem__(self, index: Tuple[int, int]) -> int: ...
+    def with_cell(self, row: int, col: int, n: int) -> Sudoku: ...
+    def solve(self) -> Sudoku: ...
+    def is_valid(self) -> bool: ...
+    def candidates(self) -> List[List[List[int]]]: ...
+    def canonical(self) -> Sudoku: ...
+    def rotate(self, turns: int = 1) -> Sudoku: ...
+    def transpose(self) -> Sudoku: ...
+    def mirror_left_right(self) -> Sudoku: ...
+    def mirror_top_bottom(self) -> Sudoku: ...
+    def permute_digits(self, digits: Sequence[int]) -> Sudoku: ...
+    def permute_rows(self, band: int, order: Sequence[int]) -> Sudoku: ...
+    def permute_columns(self, stack: int, order: Sequence[int]) -> Sudoku: ...
+    def permute_bands(self, order: Sequence[int]) -> Sudoku: ...
+    def permute_stacks(self, order: Sequence[int]) -> Sudoku: ...
+    def random_isomorph(self, seed: int) -> Sudoku: ...
+    def to_list(self) -> List[int]: ...
+    def __str__(self) -> str: ...
+    def __repr__(self) -> str: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __copy__(self) -> Sudoku: ...
+    def __deepcopy__(self, memo: Any) -> Sudoku: ...
+    def __reduce__(self) -> Tuple[type, Tuple[List[int]]]: ...
+
+class ExactCover:
+    def __init__(
+        self,
+        rows: Sequence[Sequence[int]],
+        columns: Optional[int] = None,
+        secondary: Optional[Sequence[int]] = None,
+    ) -> None: ...
+    def solve(
+        self, limit: Optional[int] = None, partial: Optional[Sequence[int]] = None
+    ) -> List[List[int]]: ...
+    def solutions(self, partial: Optional[Sequence[int]] = None) -> SolutionIter: ...
+    def events(self, partial: Optional[Sequence[int]] = None) -> EventIter: ...
+    @staticmethod
+    def from_dlx1(text: str) -> ExactCover: ...
+    def to_dlx1(self) -> str: ...
+    def __iter__(self) -> SolutionIter: ...
+    def count(
+        self,
+        partial: Optional[Sequence[int]] = None,
+        split_depth: int = 2,
+        threads: Optional[int] = None,
+    ) -> int: ...
+
+class SolutionIter(Iterator[List[int]]):
+    def __iter__(self) -> SolutionIter: ...
+    def __next__(self) -> List[int]: ...
+
+class EventIter(Iterator[_Event]):
+    def __iter__(self) -> EventIter: ...
+    def __next__(self) -> _Event: ...
+    def rows(self) -> List[int]: ...
+
+class PuzzleFile:
+    givens: Sudoku
+    placed: Sudoku
+    candidates: Optional[List[List[List[int]]]]
+    comments: List[str]
+    def __init__(
+        self,
+        givens: Sudoku,
+        placed: Optional[Sudoku] = None,
+        candidates: Optional[Sequence[Sequence[Sequence[int]]]] = None,
+        comments: Optional[Sequence[str]] = None,
+    ) -> None: ...
+    def current(self) -> Sudoku: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+
+class Book:
+    title: str
+    per_page: int
+    solutions_per_page: int
+    def __init__(
+        self, title: str, per_page: int = 4, solutions_per_page: int = 6
+    ) -> None: ...
+    def add(
+        self,
+        puzzle: Union[Sudoku, Variant, Sequence[int]],
+        title: Optional[str] = None,
+        difficulty: Optional[str] = None,
+    ) -> None: ...
+    def __len__(self) -> int: ...
+    def to_pdf(self) -> bytes: ...
+    def save(self, path: str) -> None: ...
+
+_Cell = Tuple[int, int]
+
+class Variant:
+    @property
+    def givens(self) -> Sudoku: ...
+    @property
+    def regions(self) -> List[List[int]]: ...
+    @property
+    def cages(self) -> List[Tuple[List[_Cell], Optional[int]]]: ...
+    @property
+    def thermos(self) -> List[List[_Cell]]: ...
+    @property
+    def arrows(self) -> List[Tuple[List[_Cell], List[List[_Cell]]]]: ...
+    @property
+    def dots(
+        self,
+    ) -> List[Tuple[_Cell, _Cell, Literal["difference", "ratio"], int]]: ...
+    @property
+    def diagonals(self) -> Tuple[bool, bool]: ...
+    def solve(self) -> Sudoku: ...
+    def solutions(self, limit: Optional[int] = None) -> List[Sudoku]: ...
@@ -0,0 +1,95 @@
+// Copyright 2022 Nathan Rowan
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+// either express or implied. See the License for the specific
+// language governing permissions and limitations under the License.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Output, Stdio};
+
+const PUZZLE: &str =
+    "4.673581.278.9654....2..79..624.3.......614..1.......7...3..6...17.5...46.9...2.5";
+const SOLUTION: &str =
+    "496735812278196543531284796962473158783561429154928367825349671317652984649817235";
+
+fn sudoku(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudoku"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // the binary may exit before reading all of its input, as when an
+    // argument is rejected, so write from another thread
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_owned();
+    let writer = std::thread::spawn(move || match stdin.write_all(input.as_bytes()) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => panic!("writing stdin: {e}"),
+        _ => {}
+    });
+
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn commands_test() {
+    let solved = sudoku(&["solve"], PUZZLE);
+    assert!(solved.status.success());
+    assert_eq!(stdout(&solved), format!("{SOLUTION}\n"));
+
+    // grids and lines can be mixed, separated by blank lines
+    let grid = sudoku(&["convert", "--to", "grid"], PUZZLE);
+    let input = format!("# two puzzles\n{}\n{PUZZLE}\n", stdout(&grid));
+    let counted = sudoku(&["count"], &input);
+    assert_eq!(stdout(&counted), "1\n1\n");
+
+    let cnf = sudoku(&["convert", "--to", "dimacs"], PUZZLE);
+    assert!(cnf.status.success());
+    assert!(stdout(&cnf).starts_with(&format!("c sudoku {PUZZLE} with ")));
+
+    let hint = sudoku(&["hint", "--json"], PUZZLE);
+    let hint: serde_json::Value = serde_json::from_str(stdout(&hint)).unwrap();
+    assert_eq!(hint["cell"], 8);
+    assert_eq!(hint["technique"], "hidden_single");
+
+    let generated = sudoku(&["generate", "--seed", "3", "--count", "2"], "");
+    let puzzles = stdout(&generated).to_owned();
+    assert_eq!(puzzles.lines().count(), 2);
+    let validated = sudoku(&["validate"], &puzzles);
+    assert!(validated.status.success());
+    assert_eq!(stdout(&validated), "unique\nunique\n");
+}
+
+#[test]
+fn failures_test() {
+    let empty = ".".repeat(81);
+    let validated = sudoku(&["validate", "--json"], &format!("{PUZZLE}\n{empty}\n"));
+    assert_eq!(validated.status.code(), Some(1));
+    assert!(stdout(&validated).contains(r#""status":"multiple_solutions""#));
+
+    // puzzles with many solutions fail quickly instead of listing them all
+    let open = format!("123456789{}", ".".repeat(72));
+    let solved = sudoku(&["solve"], &format!("{open}\n{PUZZLE}\n"));
+    assert_eq!(solved.status.code(), Some(1));
+    assert_eq!(stdout(&solved), format!("{SOLUTION}\n"));
+    assert!(String::from_utf8_lossy(&solved.stderr).contains("puzzle 1: multiple solutions"));
+
+    let unknown = sudoku(&["solve", "--fast"], PUZZLE);
+    assert_eq!(unknown.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("--fast"));
+}
@@ -0,0 +1,465 @@
+# Copyright 2022 Nathan Rowan
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing,
+# software distributed under the License is distributed on an
+# "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND,
+# either express or implied. See the License for the specific
+# language governing permissions and limitations under the License.
+
+import ast
+import copy
+import json
+import pathlib
+import pickle
+import warnings
+
+import pytest
+import sudoku
+
+PUZZLE = [
+    4, 0, 6, 7, 3, 5, 8, 1, 0, 2, 7, 8, 0, 9, 6, 5, 4, 0, 0, 0, 0, 2, 0, 0, 7, 9, 0,
+    0, 6, 2, 4, 0, 3, 0, 0, 0, 0, 0, 0, 0, 6, 1, 4, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7,
+    0, 0, 0, 3, 0, 0, 6, 0, 0, 0, 1, 7, 0, 5, 0, 0, 0, 4, 6, 0, 9, 0, 0, 0, 2, 0, 5,
+]
+
+STUB = pathlib.Path(__file__).parent.parent / 'sudoku.pyi'
+
+
+def is_solution(puzzle, solution):
+    rows = [solution[r * 9:r * 9 + 9] for r in range(9)]
+    cols = [solution[c::9] for c in range(9)]
+    boxes = [
+        [solution[(b // 3 * 3 + i // 3) * 9 + b % 3 * 3 + i % 3] for i in range(9)]
+        for b in range(9)
+    ]
+    givens_kept = all(p in (0, s) for p, s in zip(puzzle, solution))
+
+    return givens_kept and all(
+        sorted(group) == list(range(1, 10)) for group in rows + cols + boxes)
+
+
+def test_stub_covers_module():
+    tree = ast.parse(STUB.read_text())
+    stubbed = {
+        node.name for node in tree.body
+        if isinstance(node, (ast.FunctionDef, ast.ClassDef))
+    }
+    exported = {name for name in dir(sudoku) if not name.startswith('_')}
+
+    assert exported <= stubbed
+
+
+def test_package_ships_type_information():
+    # maturin installs the module as a package, with the stubs as
+    # __init__.pyi next to a py.typed marker
+    package = pathlib.Path(sudoku.__file__).parent
+    if not (package / '__init__.py').exists():
+        pytest.skip('sudoku is not installed as a package')
+
+    assert (package / 'py.typed').exists()
+    assert (package / '__init__.pyi').read_text() == STUB.read_text()
+
+
+def test_solve():
+    solution = sudoku.solve(PUZZLE)
+
+    assert isinstance(solution, list)
+    assert is_solution(PUZZLE, solution)
+
+    # puzzles come back in the form they were given
+    puzzle = sudoku.Sudoku(PUZZLE)
+    assert sudoku.solve(puzzle) == puzzle.solve()
+    assert isinstance(sudoku.random_solution(puzzle, 7), sudoku.Sudoku)
+    assert isinstance(sudoku.minimize(puzzle), sudoku.Sudoku)
+
+
+def test_solve_errors():
+    with pytest.raises(TypeError, match='grid length'):
+        sudoku.solve(PUZZLE[:80])
+    with pytest.raises(TypeError, match='invalid cell'):
+        sudoku.solve([10] + PUZZLE[1:])
+    with pytest.raises(TypeError, match='multiple solutions'):
+        sudoku.solve([0] * 9 + PUZZLE[9:])
+
+
+def test_solve_many():
+    results = sudoku.solve_many([PUZZLE, PUZZLE[:80], PUZZLE], threads=2)
+
+    assert results[0] == results[2] == sudoku.solve(PUZZLE)
+    assert isinstance(results[1], TypeError)
+
+    # items that aren't lists of digits only fail their own slot
+    garbage = [PUZZLE, 'not a puzzle', [-1] * 81, None, PUZZLE]
+    results = sudoku.solve_many(garbage)
+    assert results[0] == results[4] == sudoku.solve(PUZZLE)
+    assert isinstance(results[1], ValueError)
+    assert isinstance(results[2], OverflowError)
+    assert isinstance(results[3], TypeError)
+
+
+def test_solve_with_stats():
+    solution, stats = sudoku.solve_with_stats(PUZZLE)
+
+    assert solution == sudoku.solve(PUZZLE)
+    assert stats['solutions'] == 1
+    assert stats['nodes'] >= stats['max_depth'] + 1
+    assert len(stats['branching']) == stats['max_depth'] + 1
+
+
+def test_random_solution():
+    a = sudoku.random_solution([0] * 81, 7)
+
+    assert a == sudoku.random_solution([0] * 81, 7)
+    assert is_solution([0] * 81, a)
+    assert is_solution(PUZZLE, sudoku.random_solution(PUZZLE, 7))
+
+
+def test_count_solutions():
+    assert sudoku.count_solutions(PUZZLE) == 1
+    assert sudoku.count_solutions([0] * 9 + PUZZLE[9:], threads=2) > 1
+
+
+def test_verify_solution():
+    solution = sudoku.solve(PUZZLE)
+    assert sudoku.verify_solution(PUZZLE, solution) == []
+    assert sudoku.verify_solution(sudoku.Sudoku(PUZZLE), sudoku.Sudoku(solution)) == []
+
+    wrong = solution[:]
+    wrong[0], wrong[1] = wrong[1], wrong[0]
+    kinds = [v['kind'] for v in sudoku.verify_solution(PUZZLE, wrong)]
+    assert kinds[0] == 'changed_given'
+    assert kinds.count('repeated') == 2
+
+    # rows are valid regions too, while swapping two cells between boxes isn't
+    rows = [[r] * 9 for r in range(9)]
+    violations = sudoku.verify_solution(PUZZLE, solution, regions=rows)
+    assert violations == []
+    regions = [r // 27 * 3 + r % 9 // 3 for r in range(81)]
+    regions[0], regions[80] = regions[80], regions[0]
+    violations = sudoku.verify_solution(PUZZLE, solution, regions)
+    assert {(v['unit'], v['index']) for v in violations} == {('region', 0), ('region', 8)}
+    assert (8, 8) in violations[0]['cells']
+
+    with pytest.raises(TypeError):
+        sudoku.verify_solution(PUZZLE, solution[:80])
+
+
+def test_minimize():
+    redundant = sudoku.redundant_clues(PUZZLE)
+    assert redundant and all(PUZZLE[r * 9 + c] for r, c in redundant)
+    assert not sudoku.is_minimal(PUZZLE)
+
+    minimal = sudoku.minimize(PUZZLE)
+    assert sudoku.is_minimal(minimal)
+    assert sudoku.redundant_clues(minimal) == []
+    assert sudoku.solve(minimal) == sudoku.solve(PUZZLE)
+
+    symmetric = sudoku.minimize(sudoku.solve(PUZZLE), symmetry='half_turn')
+    assert [n != 0 for n in symmetric] == [n != 0 for n in symmetric[::-1]]
+
+    with pytest.raises(TypeError):
+        sudoku.minimize(PUZZLE, symmetry='spiral')
+
+
+def test_backdoor():
+    assert sudoku.backdoor(sudoku.Sudoku(PUZZLE)) == {'size': 0, 'cells': [], 'digits': []}
+
+    hard = sudoku.Sudoku(
+        '8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..')
+    assert sudoku.backdoor(hard, max_size=1) is None
+    backdoor = sudoku.backdoor(hard)
+    assert backdoor['size'] == 2
+    solution = hard.solve()
+    assert [solution[cell] for cell in backdoor['cells']] == backdoor['digits']
+
+
+def test_print_puzzle(capfd):
+    sudoku.print_puzzle(PUZZLE)
+
+    lines = capfd.readouterr().out.splitlines()
+    assert len(lines) == 11
+    assert lines[3] == '------+-------+------'
+
+
+def test_parse_and_format():
+    puzzle = sudoku.Sudoku(PUZZLE)
+
+    for style in ['line', 'dotted', 'grid', 'compact']:
+        assert sudoku.parse(sudoku.format(puzzle, style)) == puzzle
+    assert len(sudoku.format(puzzle, 'pencil').splitlines()) == 29
+    assert sudoku.format(puzzle, 'boxed').startswith('┏━━━┯')
+
+    ansi = sudoku.format(puzzle.solve(), 'ansi', givens=puzzle)
+    assert ansi.startswith('\x1b[1m4\x1b[0m \x1b[34m9\x1b[0m')
+    assert sudoku.format([1, 0, 0, 0] * 4, 'line') == '1000' * 4
+
+    assert sudoku.format(PUZZLE, 'line') == ''.join(map(str, PUZZLE))
+    assert sudoku.format(puzzle) == str(puzzle)
+    assert sudoku.Sudoku(str(puzzle)) == puzzle
+
+    with pytest.raises(ValueError, match='line 1, column 4'):
+        sudoku.parse('4.6x')
+    with pytest.raises(ValueError, match='style'):
+        sudoku.format(puzzle, 'fancy')
+    with pytest.raises(ValueError, match='80 cells'):
+        sudoku.format([0] * 80)
+
+
+def test_puzzle_files(tmp_path):
+    puzzle = sudoku.Sudoku(PUZZLE)
+    placed = sudoku.Sudoku([0, 9] + [0] * 79)
+    file = sudoku.PuzzleFile(puzzle, placed, comments=['A Nathan'])
+    file.candidates = file.current().candidates()
+
+    for ext in ['sdk', 'ss', 'sdx']:
+        sudoku.write_file(file, str(tmp_path / f'puzzle.{ext}'))
+        other = sudoku.read_file(str(tmp_path / f'puzzle.{ext}'))
+
+        assert other.givens == puzzle
+        assert other.placed[0, 1] == 9
+
+    sudoku.write_file(file, str(tmp_path / 'puzzle.txt'), format='sdk')
+    assert sudoku.read_file(str(tmp_path / 'puzzle.txt'), 'sdk').comments == ['A Nathan']
+
+    (tmp_path / 'bad.ss').write_text('4.6735812\n278x96540\n')
+    with pytest.raises(ValueError, match='line 2, column 4'):
+        sudoku.read_file(str(tmp_path / 'bad.ss'))
+    with pytest.raises(OSError):
+        sudoku.read_file(str(tmp_path / 'missing.sdk'))
+
+    bad = [[[10] if r == c == 0 else [] for c in range(9)] for r in range(9)]
+    with pytest.raises(ValueError, match='between 1 and 9'):
+        file.candidates = bad
+
+
+def test_import_fpuzzles():
+    givens = PUZZLE[9:]
+    grid = [{} for _ in range(9)] + [
+        {'value': n, 'given': True} if n else {} for n in givens
+    ]
+    puzzle = {
+        'size': 9,
+        'title': 'Test',
+        'grid': [grid[r * 9:r * 9 + 9] for r in range(9)],
+        'killercage': [
+            {'cells': ['R1C1', 'R1C2', 'R1C3'], 'value': '19'},
+            {'cells': ['R1C4', 'R1C5'], 'value': '10'},
+        ],
+        'thermometer': [{'lines': [['R3C3', 'R3C4', 'R3C5']]}],
+        'arrow': [{'cells': ['R1C2'], 'lines': [['R1C2', 'R2C3', 'R3C3']]}],
+        'difference': [{'cells': ['R1C8', 'R1C9']}],
+        'ratio': [{'cells': ['R2C4', 'R3C4']}],
+    }
+
+    variant = sudoku.import_fpuzzles(json.dumps(puzzle))
+    assert variant.cages[0] == ([(0, 0), (0, 1), (0, 2)], 19)
+    assert variant.arrows == [([(0, 1)], [[(1, 2), (2, 2)]])]
+    assert variant.dots[1] == ((1, 3), (2, 3), 'ratio', 2)
+    assert variant.solve().to_list() == sudoku.solve(PUZZLE)
+
+    puzzle['antiknight'] = True
+    with pytest.raises(ValueError, match='antiknight'):
+        sudoku.import_fpuzzles(json.dumps(puzzle))
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter('always')
+        sudoku.import_fpuzzles(json.dumps(puzzle), skip_unsupported=True)
+    assert 'antiknight' in str(caught[0].message)
+
+
+def test_render():
+    solution = sudoku.solve(PUZZLE)
+
+    svg = sudoku.render_svg(PUZZLE, solution, highlights=[(0, 0)], cell_size=30)
+    assert svg.startswith('<svg')
+    assert 'width="285"' in svg
+    assert svg == sudoku.render_svg(sudoku.Sudoku(PUZZLE), sudoku.Sudoku(solution),
+                                    highlights=[(0, 0)], cell_size=30)
+
+    png = sudoku.render_png(PUZZLE, pencil_marks=True)
+    assert png.startswith(b'\x89PNG\r\n\x1a\n')
+
+    with pytest.raises(ValueError, match='font file'):
+        sudoku.render_png(PUZZLE, font_file='missing.ttf')
+
+
+def test_book(tmp_path):
+    book = sudoku.Book('Weekly', per_page=2)
+    book.add(PUZZLE, difficulty='Easy')
+    book.add(sudoku.Sudoku(PUZZLE), title='Second')
+    book.add(sudoku.Sudoku(sudoku.solve(PUZZLE)), title='Solved', difficulty='Trivial')
+    assert len(book) == 3
+
+    pdf = book.to_pdf()
+    assert pdf.startswith(b'%PDF')
+    book.save(str(tmp_path / 'book.pdf'))
+    assert (tmp_path / 'book.pdf').read_bytes().startswith(b'%PDF')
+
+    book.add([0] * 81)
+    with pytest.raises(ValueError, match='solve'):
+        book.to_pdf()
+
+
+def test_sudoku_class():
+    puzzle = sudoku.Sudoku(PUZZLE)
+    solution = puzzle.solve()
+
+    assert puzzle[0, 0] == 4
+    assert solution.to_list() == sudoku.solve(PUZZLE)
+    assert puzzle.is_valid()
+    assert puzzle.candidates()[0][1] == [9]
+    assert str(puzzle).splitlines()[3] == '------+-------+------'
+    assert sudoku.Sudoku(repr(puzzle)[8:-2]) == puzzle
+    assert sudoku.Sudoku([PUZZLE[r * 9:r * 9 + 9] for r in range(9)]) == puzzle
+
+    with pytest.raises(IndexError):
+        puzzle[9, 0]
+
+    # puzzles are immutable, so changing a cell makes a copy
+    changed = puzzle.with_cell(0, 1, 4)
+    assert not changed.is_valid()
+    assert puzzle.is_valid()
+    with pytest.raises(TypeError):
+        puzzle[0, 1] = 4
+    with pytest.raises(IndexError):
+        puzzle.with_cell(9, 0, 1)
+
+
+def test_canonical():
+    puzzle = sudoku.Sudoku(PUZZLE)
+    relabeled = sudoku.Sudoku([10 - n if n else 0 for n in PUZZLE])
+    transposed = sudoku.Sudoku([PUZZLE[c * 9 + r] for r in range(9) for c in range(9)])
+
+    assert len({puzzle.canonical(), relabeled.canonical(), transposed.canonical()}) == 1
+    assert puzzle.canonical().canonical() == puzzle.canonical()
+    assert sudoku.are_equivalent(puzzle, transposed)
+    assert not sudoku.are_equivalent(puzzle, sudoku.Sudoku([0] * 81))
+
+
+def test_transformations():
+    puzzle = sudoku.Sudoku(PUZZLE)
+    solution = puzzle.solve()
+
+    assert puzzle.rotate()[0, 8] == puzzle[0, 0]
+    assert puzzle.rotate(2) == puzzle.mirror_left_right().mirror_top_bottom()
+    assert puzzle.transpose().transpose() == puzzle
+    assert puzzle.permute_bands([1, 0, 2])[0, 0] == puzzle[3, 0]
+    assert puzzle.permute_stacks([1, 0, 2])[0, 0] == puzzle[0, 3]
+    assert puzzle.permute_rows(0, [1, 0, 2])[0, 0] == puzzle[1, 0]
+    assert puzzle.permute_columns(0, [1, 0, 2])[0, 0] == puzzle[0, 1]
+    assert puzzle.permute_digits([9, 8, 7, 6, 5, 4, 3, 2, 1])[0, 0] == 6
+
+    isomorph = puzzle.random_isomorph(3)
+    assert isomorph == puzzle.random_isomorph(3)
+    assert isomorph.solve() == solution.random_isomorph(3)
+    assert sudoku.are_equivalent(isomorph, puzzle)
+
+    with pytest.raises(TypeError):
+        puzzle.permute_rows(3, [0, 1, 2])
+    with pytest.raises(TypeError):
+        puzzle.permute_digits([1] * 9)
+
+
+def test_sudoku_copy_and_pickle():
+    puzzle = sudoku.Sudoku(PUZZLE)
+
+    for other in [copy.copy(puzzle), copy.deepcopy(puzzle),
+                  pickle.loads(pickle.dumps(puzzle))]:
+        assert other == puzzle
+        assert hash(other) == hash(puzzle)
+        assert other is not puzzle
+
+    assert len({puzzle, copy.copy(puzzle)}) == 1
+    assert puzzle != sudoku.Sudoku([0] * 81)
+
+
+def test_numpy_arrays():
+    np = pytest.importorskip('numpy')
+
+    for shape in [(81,), (9, 9)]:
+        for dtype in [np.int8, np.int64, np.uint16]:
+            puzzle = np.array(PUZZLE, dtype=dtype).reshape(shape)
+            solution = sudoku.solve(puzzle)
+
+            assert solution.shape == shape
+            assert solution.dtype == dtype
+            assert solution.ravel().tolist() == sudoku.solve(PUZZLE)
+
+    assert sudoku.Sudoku(np.array(PUZZLE).reshape(9, 9)) == sudoku.Sudoku(PUZZLE)
+
+    with pytest.raises(sudoku.ShapeError):
+        sudoku.solve(np.array(PUZZLE[:80]))
+    with pytest.raises(sudoku.DtypeError):
+        sudoku.solve(np.array(PUZZLE, dtype=float))
+
+
+def test_exact_cover():
+    rows = [[0, 1], [2], [0], [1, 3], [2, 3]]
+
+    problem = sudoku.ExactCover(rows)
+    assert problem.solve() == [[0, 4], [2, 3, 1]]
+    assert list(problem) == problem.solve()
+
+    problem = sudoku.ExactCover(rows, secondary=[3])
+    assert problem.solve() == [[0, 1], [0, 4], [2, 3, 1]]
+    assert problem.solve(limit=1) == [[0, 1]]
+    assert problem.solve(partial=[4]) == [[4, 0]]
+    assert problem.count() == 3
+
+    solutions = problem.solutions()
+    assert next(solutions) == [0, 1]
+    assert list(solutions) == [[0, 4], [2, 3, 1]]
+
+    with pytest.raises(ValueError, match='overlaps'):
+        problem.solve(partial=[0, 2])
+    with pytest.raises(ValueError):
+        sudoku.ExactCover([[0, 0]])
+
+
+def test_search_events():
+    problem = sudoku.ExactCover([[0, 1], [2], [0], [1, 3], [2, 3]])
+    events = problem.events()
+    assert next(events) == {'kind': 'choose_column', 'column': 0, 'size': 2, 'depth': 0}
+
+    # the search picks up where it was paused
+    rest = list(events)
+    assert events.rows() == []
+    solutions = [e['rows'] for e in rest if e['kind'] == 'solution']
+    assert solutions == problem.solve()
+
+    events = sudoku.search_events(PUZZLE)
+    assert len(events.rows()) == sum(1 for n in PUZZLE if n)
+    solution = sudoku.solve(PUZZLE)
+    for event in events:
+        if event['kind'] == 'try_row':
+            row, col = event['cell']
+            assert solution[row * 9 + col] == event['digit']
+        elif event['kind'] == 'solution':
+            assert event['grid'] == solution
+
+
+def test_interop():
+    problem = sudoku.ExactCover([[0, 1], [2], [0], [1, 3], [2, 3]], secondary=[3])
+    text = problem.to_dlx1()
+    assert text.splitlines()[0] == 'c0 c1 c2 | c3'
+    assert sudoku.ExactCover.from_dlx1(text).solve() == problem.solve()
+
+    named = sudoku.ExactCover.from_dlx1('| comment\nA B\nA\nB\nA B\n')
+    assert sorted(named.solve()) == [[0, 1], [2]]
+    with pytest.raises(ValueError):
+        sudoku.ExactCover.from_dlx1('A B\nC\n')
+
+    cnf = sudoku.to_dimacs(sudoku.Sudoku(PUZZLE))
+    assert cnf.splitlines()[1].startswith('p cnf 729 ')
+
+    solution = sudoku.solve(PUZZLE)
+    literals = [i * 9 + n for i, n in enumerate(solution)]
+    model = 's SATISFIABLE\nv ' + ' '.join(map(str, literals)) + ' 0\n'
+    assert sudoku.parse_dimacs_model(model) == sudoku.Sudoku(solution)
+    with pytest.raises(ValueError):
+        sudoku.parse_dimacs_model('s UNSATISFIABLE\n')